@@ -0,0 +1,301 @@
+//! This file contains the arena generator, which lays out obstacles on the board for a round:
+//! scattered pillars, rooms joined by doorways, mazes or symmetric patterns.  Like the spawns,
+//! every layout is made from a seed and mirrored across the center lines of the board, and a
+//! layout is only used once a flood fill over the board has shown that every player can reach
+//! every other and has as much room of their own as anybody else.
+
+use crate::graphics::Block;
+use crate::settings::ArenaStyle;
+use crate::spawn;
+use crate::spawn::Spawn;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+
+/// The smallest width and height of a quarter of the board, in Blocks, that obstacles are
+/// placed in.  Smaller boards are left open.
+const MIN_QUARTER: u32 = 8;
+
+/// The number of layouts tried before giving up on a board.
+const MAX_ATTEMPTS: usize = 50;
+
+/// The largest difference allowed between the areas the players can reach before anybody
+/// else, as a fraction of the area of the board that isn't covered by obstacles.
+const AREA_TOLERANCE: f64 = 0.05;
+
+/// The distance between the posts of a maze, in Blocks.
+const MAZE_PITCH: u32 = 4;
+
+/// The width of a doorway between rooms, in Blocks.
+const DOORWAY_WIDTH: u32 = 3;
+
+/// The obstacles of an arena and the spawns of the players taking part in it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Arena {
+    pub obstacles: Vec<Block>,
+    pub spawns: Vec<Spawn>,
+}
+
+/// Generates an arena in the given style for the given number of players on a board of the
+/// given size from the given seed, which always gives the same arena.  Returns None if no
+/// fair arena could be found, e.g. because the board is too small.
+///
+/// Three players can't split a mirrored board evenly, so their arena is checked as if a
+/// fourth player were there, and the quarter it would have had is left for them to fight
+/// over.
+pub fn generate(
+    style: ArenaStyle,
+    count: usize,
+    width: u32,
+    height: u32,
+    seed: u64,
+) -> Option<Arena> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let checked = if count == 3 { 4 } else { count };
+    (0..MAX_ATTEMPTS).find_map(|_| {
+        let obstacles = mirrored(&layout(style, width, height, &mut rng), width, height);
+        let mut spawns = spawn::symmetric_spawns(checked, width, height, &obstacles, rng.gen())?;
+        if is_fair(&obstacles, &spawns, width, height) {
+            spawns.truncate(count);
+            Some(Arena { obstacles, spawns })
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns the obstacles of a layout in the given style for the top left quarter of the
+/// board, up to and including its center lines.  They are mirrored into the other quarters
+/// by `mirrored`.
+fn layout(style: ArenaStyle, width: u32, height: u32, rng: &mut ChaCha8Rng) -> Vec<Block> {
+    // the last column and row of the quarter, which lie on the center lines of the board
+    let (last_x, last_y) = ((width - 1) / 2, (height - 1) / 2);
+    if last_x < MIN_QUARTER || last_y < MIN_QUARTER {
+        return Vec::new();
+    }
+    let mut blocks: Vec<(u32, u32)> = Vec::new();
+    match style {
+        ArenaStyle::Open => {}
+        ArenaStyle::Pillars => {
+            // 2x2 pillars, kept away from the walls so nobody is trapped in a corner
+            for _ in 0..(last_x * last_y) / 40 {
+                let x = rng.gen_range(3..last_x);
+                let y = rng.gen_range(3..last_y);
+                blocks.extend([(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]);
+            }
+        }
+        ArenaStyle::Rooms => {
+            // walls along the center lines split the board into four rooms, each with a
+            // doorway into the rooms beside it
+            let door_x = rng.gen_range(2..last_x - DOORWAY_WIDTH);
+            let door_y = rng.gen_range(2..last_y - DOORWAY_WIDTH);
+            let doorway = |start: u32, at: u32| (start..start + DOORWAY_WIDTH).contains(&at);
+            blocks.extend(
+                (1..=last_x)
+                    .filter(|&x| !doorway(door_x, x))
+                    .map(|x| (x, last_y)),
+            );
+            blocks.extend(
+                (1..=last_y)
+                    .filter(|&y| !doorway(door_y, y))
+                    .map(|y| (last_x, y)),
+            );
+        }
+        ArenaStyle::Maze => {
+            // posts at regular intervals, each joined to the next post to its right or below
+            // it by a wall, leaving the center lines open so the quarters stay connected
+            for x in (MAZE_PITCH..last_x - 1).step_by(MAZE_PITCH as usize) {
+                for y in (MAZE_PITCH..last_y - 1).step_by(MAZE_PITCH as usize) {
+                    blocks.push((x, y));
+                    let (dx, dy) = if rng.gen_bool(0.5) { (1, 0) } else { (0, 1) };
+                    if rng.gen_bool(0.7) {
+                        for step in 1..MAZE_PITCH {
+                            blocks.push((x + dx * step, y + dy * step));
+                        }
+                    }
+                }
+            }
+        }
+        ArenaStyle::Pattern => {
+            // straight lines of a few Blocks, which form a pattern once mirrored
+            for _ in 0..rng.gen_range(3..=6) {
+                let (x, y) = (rng.gen_range(3..=last_x), rng.gen_range(3..=last_y));
+                let length = rng.gen_range(2..=6);
+                let horizontal = rng.gen_bool(0.5);
+                for step in 0..length {
+                    let (x, y) = if horizontal {
+                        (x + step, y)
+                    } else {
+                        (x, y + step)
+                    };
+                    if x <= last_x && y <= last_y {
+                        blocks.push((x, y));
+                    }
+                }
+            }
+        }
+    }
+    blocks.into_iter().map(|(x, y)| Block { x, y }).collect()
+}
+
+/// Returns the given obstacles together with their mirror images across both center lines
+/// of the board, without duplicates.
+fn mirrored(blocks: &[Block], width: u32, height: u32) -> Vec<Block> {
+    let mut obstacles = Vec::new();
+    for &Block { x, y } in blocks {
+        for (x, y) in [
+            (x, y),
+            (width - 1 - x, y),
+            (x, height - 1 - y),
+            (width - 1 - x, height - 1 - y),
+        ] {
+            let block = Block { x, y };
+            if !obstacles.contains(&block) {
+                obstacles.push(block);
+            }
+        }
+    }
+    obstacles
+}
+
+/// Checks with a flood fill from every player's head that all players can reach each other,
+/// and that the areas each player can reach before anybody else are the same size, give or
+/// take `AREA_TOLERANCE`.
+fn is_fair(obstacles: &[Block], spawns: &[Spawn], width: u32, height: u32) -> bool {
+    let (width, height) = (width as usize, height as usize);
+    let index = |block: Block| block.y as usize * width + block.x as usize;
+    let mut free: Vec<bool> = (0..width * height)
+        .map(|position| {
+            let (x, y) = (position % width, position / width);
+            x > 0 && y > 0 && x < width - 1 && y < height - 1
+        })
+        .collect();
+    let trails = spawns.iter().flat_map(|spawn| spawn.trail());
+    for block in obstacles.iter().copied().chain(trails) {
+        free[index(block)] = false;
+    }
+
+    // the distance from each player's head to every Block, or None if it can't be reached
+    let distances: Vec<Vec<Option<usize>>> = spawns
+        .iter()
+        .map(|spawn| {
+            let mut distance = vec![None; width * height];
+            let mut queue = VecDeque::from([index(spawn.head)]);
+            distance[index(spawn.head)] = Some(0);
+            while let Some(position) = queue.pop_front() {
+                // the wall around the board is never free, so every neighbor is on the board
+                for next in [
+                    position - 1,
+                    position + 1,
+                    position - width,
+                    position + width,
+                ] {
+                    if free[next] && distance[next].is_none() {
+                        distance[next] = distance[position].map(|d| d + 1);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            distance
+        })
+        .collect();
+
+    // a player's head counts as reachable if the Blocks in front of it are
+    let reaches = |from: &[Option<usize>], spawn: &Spawn| {
+        let (dx, dy) = spawn.direction.offset();
+        let ahead = Block {
+            x: (spawn.head.x as i32 + dx) as u32,
+            y: (spawn.head.y as i32 + dy) as u32,
+        };
+        from[index(ahead)].is_some()
+    };
+    if !distances
+        .iter()
+        .all(|from| spawns.iter().all(|spawn| reaches(from, spawn)))
+    {
+        return false;
+    }
+
+    // each Block belongs to the player who can get there first, or to nobody on a tie
+    let mut areas = vec![0; spawns.len()];
+    for position in 0..width * height {
+        let reached: Vec<Option<usize>> = distances.iter().map(|from| from[position]).collect();
+        if let Some(&nearest) = reached.iter().flatten().min() {
+            let mut closest = (0..reached.len()).filter(|&player| reached[player] == Some(nearest));
+            if let (Some(player), None) = (closest.next(), closest.next()) {
+                areas[player] += 1;
+            }
+        }
+    }
+    let open = free.iter().filter(|&&free| free).count();
+    let (smallest, largest) = (areas.iter().min().unwrap(), areas.iter().max().unwrap());
+    (largest - smallest) as f64 <= AREA_TOLERANCE * open as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Direction;
+
+    #[test]
+    fn test_generate() {
+        let (width, height) = (35, 25);
+        for style in ArenaStyle::ALL {
+            for count in 2..=4 {
+                for seed in 0..10 {
+                    let arena = generate(style, count, width, height, seed).unwrap();
+                    assert_eq!(count, arena.spawns.len());
+                    assert_eq!(
+                        Some(&arena),
+                        generate(style, count, width, height, seed).as_ref()
+                    );
+                    for spawn in &arena.spawns {
+                        for block in spawn.trail() {
+                            assert!(!arena.obstacles.contains(&block));
+                        }
+                    }
+                    // the obstacles are the same in every quarter of the board
+                    for &Block { x, y } in &arena.obstacles {
+                        let mirror = Block {
+                            x: width - 1 - x,
+                            y: height - 1 - y,
+                        };
+                        assert!(arena.obstacles.contains(&mirror));
+                    }
+                }
+            }
+        }
+        assert!(generate(ArenaStyle::Open, 2, 35, 25, 0)
+            .unwrap()
+            .obstacles
+            .is_empty());
+        assert!(!generate(ArenaStyle::Maze, 2, 35, 25, 0)
+            .unwrap()
+            .obstacles
+            .is_empty());
+    }
+
+    #[test]
+    fn test_is_fair() {
+        let spawns = [
+            Spawn {
+                head: Block { x: 10, y: 5 },
+                direction: Direction::Right,
+            },
+            Spawn {
+                head: Block { x: 24, y: 19 },
+                direction: Direction::Left,
+            },
+        ];
+        assert!(is_fair(&[], &spawns, 35, 25));
+        // a wall across the middle of the board keeps the players apart
+        let wall: Vec<Block> = (1..34).map(|x| Block { x, y: 12 }).collect();
+        assert!(!is_fair(&wall, &spawns, 35, 25));
+        // and one that boxes player 2 into a corner with a doorway leaves player 1 more room
+        let corner: Vec<Block> = (16..34)
+            .map(|x| Block { x, y: 14 })
+            .chain((15..24).filter(|&y| y != 20).map(|y| Block { x: 16, y }))
+            .collect();
+        assert!(!is_fair(&corner, &spawns, 35, 25));
+    }
+}
@@ -0,0 +1,502 @@
+//! This file contains the sound of the game: clicks when a player turns, a bang when a player
+//! crashes, a fanfare for the winner, beeps counting down to the next ring closing in sudden
+//! death, and a low engine hum and optional music while a round is being played.  Like the
+//! visual effects, sounds are triggered by the events of the Game and never change it.  Every
+//! sound is synthesized when the game starts, so no sound files are needed.
+//!
+//! Sounds are played through an AudioBackend.  The real backend uses rodio and is only built
+//! with the `sound` feature; without it, or if there is no sound device, the NullAudio
+//! backend quietly ignores everything, so headless builds and tests are unaffected.
+
+use crate::event::GameEvent;
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+
+/// The number of samples per second of every synthesized sound.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// The volume of the engine hum relative to the sound effects, which would be tiring at
+/// full volume.
+const HUM_LEVEL: f32 = 0.15;
+
+/// The volume of the music relative to the sound effects.
+const MUSIC_LEVEL: f32 = 0.35;
+
+/// The seconds before a ring closes in sudden death at which a beep is played.  The
+/// countdown at the start of a round beeps on every whole second instead.
+const COUNTDOWN_BEEPS: [f64; 3] = [3.0, 2.0, 1.0];
+
+/// The sound effects, each played once when something happens in the game.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Sound {
+    Turn,    // a player steered by a person has turned
+    Crash,   // a player has crashed
+    Victory, // the round has been won
+    Beep,    // a second of a countdown has passed
+}
+
+/// The sounds that are played on a loop.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Loop {
+    Hum,   // the engines of the players, while a round is being played
+    Music, // background music, if it is switched on
+}
+
+impl Sound {
+    /// Every sound effect.
+    pub const ALL: [Sound; 4] = [Sound::Turn, Sound::Crash, Sound::Victory, Sound::Beep];
+
+    /// Synthesizes the samples of this sound, at `SAMPLE_RATE` samples per second.
+    pub fn samples(self) -> Vec<f32> {
+        match self {
+            Sound::Turn => tone(1200.0, 0.03, Wave::Square),
+            Sound::Crash => {
+                let mut noise = Noise(0x2545_f491);
+                let rumble = tone(70.0, 0.5, Wave::Sine);
+                rumble
+                    .iter()
+                    .enumerate()
+                    .map(|(index, sample)| {
+                        let fade = 1.0 - index as f32 / rumble.len() as f32;
+                        (noise.next() * fade * fade + sample) * 0.5
+                    })
+                    .collect()
+            }
+            Sound::Victory => {
+                // a rising arpeggio of C major, holding the last note
+                let notes = [
+                    (523.25, 0.12),
+                    (659.25, 0.12),
+                    (783.99, 0.12),
+                    (1046.5, 0.4),
+                ];
+                notes
+                    .iter()
+                    .flat_map(|&(frequency, seconds)| tone(frequency, seconds, Wave::Triangle))
+                    .collect()
+            }
+            Sound::Beep => tone(880.0, 0.12, Wave::Sine),
+        }
+    }
+}
+
+impl Loop {
+    /// Every looping sound.
+    pub const ALL: [Loop; 2] = [Loop::Hum, Loop::Music];
+
+    /// Synthesizes the samples of this sound, at `SAMPLE_RATE` samples per second.  The
+    /// samples can be repeated without a click at the join: the hum holds a whole number of
+    /// periods of its waves, and every note of the music fades out before the next.
+    pub fn samples(self) -> Vec<f32> {
+        match self {
+            Loop::Hum => (0..SAMPLE_RATE)
+                .map(|index| {
+                    let time = index as f32 / SAMPLE_RATE as f32;
+                    let saw = |frequency: f32| 2.0 * (time * frequency).fract() - 1.0;
+                    0.6 * saw(55.0) + 0.4 * saw(110.0)
+                })
+                .collect(),
+            Loop::Music => {
+                // eighth notes at 120 beats per minute, arpeggiating A minor, F, C and G
+                let chords = [
+                    [220.0, 261.63, 329.63, 440.0],
+                    [174.61, 220.0, 261.63, 349.23],
+                    [261.63, 329.63, 392.0, 523.25],
+                    [196.0, 246.94, 293.66, 392.0],
+                ];
+                chords
+                    .iter()
+                    .flat_map(|chord| [0, 1, 2, 3, 2, 1, 2, 3].map(|note| chord[note]))
+                    .flat_map(|frequency| tone(frequency, 0.25, Wave::Triangle))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The shapes of wave a tone can be made of.
+#[derive(Copy, Clone)]
+enum Wave {
+    Sine,
+    Square,
+    Triangle,
+}
+
+/// Synthesizes a tone of the given frequency and length that fades out towards its end, so
+/// tones can follow one another without clicking.
+fn tone(frequency: f32, seconds: f32, wave: Wave) -> Vec<f32> {
+    let count = (seconds * SAMPLE_RATE as f32) as usize;
+    (0..count)
+        .map(|index| {
+            let phase = (index as f32 * frequency / SAMPLE_RATE as f32).fract();
+            let value = match wave {
+                Wave::Sine => (phase * std::f32::consts::TAU).sin(),
+                Wave::Square if phase < 0.5 => 0.5,
+                Wave::Square => -0.5,
+                Wave::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            };
+            value * (1.0 - index as f32 / count as f32)
+        })
+        .collect()
+}
+
+/// A small xorshift generator of white noise.  It always starts from the same seed, so the
+/// crash sounds the same every time and doesn't need the random number generator.
+struct Noise(u32);
+
+impl Noise {
+    /// Returns the next sample of noise, between -1 and 1.
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// The volume options, which are remembered in the config file.  Volumes go from 0 to 1.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub volume: f32,
+    pub music: bool, // whether background music is played
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            volume: 0.8,
+            music: false,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Raises the volume by a tenth, or lowers it if `up` is false, staying between 0 and 1.
+    pub fn step_volume(&mut self, up: bool) {
+        let tenths = (self.volume * 10.0).round() + if up { 1.0 } else { -1.0 };
+        self.volume = tenths.clamp(0.0, 10.0) / 10.0;
+    }
+}
+
+/// Something that can play the sounds of the game.
+pub trait AudioBackend {
+    /// Plays a sound effect once, at the given volume.
+    fn play(&mut self, sound: Sound, volume: f32);
+
+    /// Changes the volume of a looping sound, which keeps playing in the background from the
+    /// moment the backend is created.  A volume of 0 silences it.
+    fn set_loop_volume(&mut self, sound: Loop, volume: f32);
+}
+
+/// A backend that plays nothing, for builds without sound and for machines without a sound
+/// device.
+pub struct NullAudio;
+
+impl AudioBackend for NullAudio {
+    fn play(&mut self, _sound: Sound, _volume: f32) {}
+
+    fn set_loop_volume(&mut self, _sound: Loop, _volume: f32) {}
+}
+
+/// Returns the backend sounds should be played through: the sound device if the game was
+/// built with the `sound` feature and one can be opened, and NullAudio otherwise.
+pub fn default_backend() -> Box<dyn AudioBackend> {
+    #[cfg(all(feature = "sound", not(target_arch = "wasm32")))]
+    match rodio_backend::RodioAudio::new() {
+        Ok(backend) => return Box::new(backend),
+        Err(err) => eprintln!("Playing without sound: {}", err),
+    }
+    Box::new(NullAudio)
+}
+
+/// Audio watches the Game and plays the sounds for whatever has happened since the last
+/// update through its backend, at the volumes of its settings.
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+    settings: AudioSettings,
+    paused: bool, // true while the game is paused, which silences the hum and the music
+
+    in_round: bool, // true while a round is being played, when the hum is heard
+    countdown: Option<f64>, // the sudden-death countdown at the last update
+    start_countdown: Option<f64>, // the countdown at the start of the round at the last update
+    loop_volumes: [f32; 2], // the volumes last given to the backend for each Loop
+}
+
+impl Audio {
+    /// Creates an Audio playing through the given backend at the volumes of the given
+    /// settings.
+    pub fn new(backend: Box<dyn AudioBackend>, settings: AudioSettings) -> Self {
+        Audio {
+            backend,
+            settings,
+            paused: false,
+            in_round: false,
+            countdown: None,
+            start_countdown: None,
+            loop_volumes: [0.0; 2],
+        }
+    }
+
+    /// Returns the volume settings.
+    pub fn settings(&self) -> AudioSettings {
+        self.settings
+    }
+
+    /// Changes the volume settings.
+    pub fn set_settings(&mut self, settings: AudioSettings) {
+        self.settings = settings;
+        self.update_loops();
+    }
+
+    /// Switches all sound off, or back on.
+    pub fn toggle_mute(&mut self) {
+        self.settings.muted = !self.settings.muted;
+        self.update_loops();
+    }
+
+    /// Pauses or resumes the hum and the music, e.g. while a menu is open.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.update_loops();
+    }
+
+    /// Plays the sounds of the given events, which the given Game reported since the last
+    /// update, and the beeps of the countdowns at the start of the round and in sudden
+    /// death.
+    pub fn update(&mut self, game: &Game, events: &[GameEvent]) {
+        for event in events {
+            match *event {
+                GameEvent::Turned { player, .. } if !game.players()[player].is_ai() => {
+                    self.play(Sound::Turn)
+                }
+                GameEvent::Crashed { .. } => self.play(Sound::Crash),
+                GameEvent::RoundOver { winner } if winner.is_some() => self.play(Sound::Victory),
+                _ => {}
+            }
+        }
+        self.in_round = !game.is_game_over();
+
+        let countdown = game.sudden_death_countdown();
+        if let (Some(before), Some(now)) = (self.countdown, countdown) {
+            if COUNTDOWN_BEEPS
+                .iter()
+                .any(|&beep| before > beep && now <= beep)
+            {
+                self.play(Sound::Beep);
+            }
+        }
+        self.countdown = countdown;
+
+        let start_countdown = game.countdown();
+        if let Some(now) = start_countdown {
+            if self
+                .start_countdown
+                .is_none_or(|before| now.ceil() < before.ceil())
+            {
+                self.play(Sound::Beep);
+            }
+        }
+        self.start_countdown = start_countdown;
+
+        self.update_loops();
+    }
+
+    /// Plays a sound effect at the volume of the settings, unless sound is muted.
+    fn play(&mut self, sound: Sound) {
+        if !self.settings.muted && self.settings.volume > 0.0 {
+            self.backend.play(sound, self.settings.volume);
+        }
+    }
+
+    /// Gives the backend the volume each looping sound should now be playing at.  The hum is
+    /// only heard while a round is being played, and neither loop is heard while paused.
+    fn update_loops(&mut self) {
+        let volume = if self.settings.muted || self.paused {
+            0.0
+        } else {
+            self.settings.volume
+        };
+        let volumes = [
+            if self.in_round {
+                volume * HUM_LEVEL
+            } else {
+                0.0
+            },
+            if self.settings.music {
+                volume * MUSIC_LEVEL
+            } else {
+                0.0
+            },
+        ];
+        for (index, &sound) in Loop::ALL.iter().enumerate() {
+            if volumes[index] != self.loop_volumes[index] {
+                self.loop_volumes[index] = volumes[index];
+                self.backend.set_loop_volume(sound, volumes[index]);
+            }
+        }
+    }
+}
+
+/// The backend playing sounds on the sound device through rodio.
+#[cfg(all(feature = "sound", not(target_arch = "wasm32")))]
+mod rodio_backend {
+    use super::{AudioBackend, Loop, Sound, SAMPLE_RATE};
+    use rodio::buffer::SamplesBuffer;
+    use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+
+    pub struct RodioAudio {
+        _stream: OutputStream, // the sound device, which stops playing when dropped
+        handle: OutputStreamHandle,
+        sounds: Vec<Vec<f32>>, // the samples of each Sound, in the order of Sound::ALL
+        loops: Vec<Sink>,      // the sink playing each Loop, in the order of Loop::ALL
+    }
+
+    impl RodioAudio {
+        /// Opens the default sound device, synthesizes every sound and starts the loops
+        /// playing silently.
+        pub fn new() -> Result<Self, String> {
+            let (stream, handle) = OutputStream::try_default().map_err(|err| err.to_string())?;
+            let mut loops = Vec::new();
+            for sound in Loop::ALL {
+                let sink = Sink::try_new(&handle).map_err(|err| err.to_string())?;
+                sink.set_volume(0.0);
+                sink.append(SamplesBuffer::new(1, SAMPLE_RATE, sound.samples()).repeat_infinite());
+                loops.push(sink);
+            }
+            Ok(RodioAudio {
+                _stream: stream,
+                handle,
+                sounds: Sound::ALL.iter().map(|sound| sound.samples()).collect(),
+                loops,
+            })
+        }
+    }
+
+    impl AudioBackend for RodioAudio {
+        fn play(&mut self, sound: Sound, volume: f32) {
+            let index = Sound::ALL.iter().position(|&s| s == sound).unwrap();
+            let samples = SamplesBuffer::new(1, SAMPLE_RATE, self.sounds[index].clone());
+            // a sound that can't be played is simply not heard
+            let _ = self.handle.play_raw(samples.amplify(volume));
+        }
+
+        fn set_loop_volume(&mut self, sound: Loop, volume: f32) {
+            let index = Loop::ALL.iter().position(|&l| l == sound).unwrap();
+            self.loops[index].set_volume(volume);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER_SPEED;
+    use crate::settings::{Settings, SuddenDeath};
+    use input::Key;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A backend that remembers every sound effect it is asked to play.
+    struct Recorder(Rc<RefCell<Vec<Sound>>>);
+
+    impl AudioBackend for Recorder {
+        fn play(&mut self, sound: Sound, _volume: f32) {
+            self.0.borrow_mut().push(sound);
+        }
+
+        fn set_loop_volume(&mut self, _sound: Loop, _volume: f32) {}
+    }
+
+    #[test]
+    fn test_samples() {
+        for sound in Sound::ALL {
+            let samples = sound.samples();
+            assert!(!samples.is_empty());
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+        }
+        for sound in Loop::ALL {
+            let samples = sound.samples();
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+        }
+        assert_eq!(SAMPLE_RATE as usize, Loop::Hum.samples().len());
+    }
+
+    #[test]
+    fn test_step_volume() {
+        let mut settings = AudioSettings::default();
+        settings.step_volume(true);
+        settings.step_volume(true);
+        settings.step_volume(true);
+        assert_eq!(1.0, settings.volume);
+        settings.volume = 0.1;
+        settings.step_volume(false);
+        settings.step_volume(false);
+        assert_eq!(0.0, settings.volume);
+    }
+
+    #[test]
+    fn test_update() {
+        let played = Rc::new(RefCell::new(Vec::new()));
+        let backend = Box::new(Recorder(played.clone()));
+        let mut audio = Audio::new(backend, AudioSettings::default());
+        let settings = Settings {
+            sudden_death: Some(SuddenDeath {
+                start_after: 50,
+                interval: 10,
+            }),
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        let events = game.take_events();
+        audio.update(&game, &events);
+
+        // the countdown at the start of the round beeps for 3, 2 and 1
+        for _ in 0..2 {
+            game.update(1.0);
+            let events = game.take_events();
+            audio.update(&game, &events);
+        }
+        assert_eq!(vec![Sound::Beep; 3], *played.borrow());
+        played.borrow_mut().clear();
+        game.skip_countdown();
+
+        game.update(PLAYER_SPEED + 0.01);
+        game.key_pressed(Key::S);
+        let events = game.take_events();
+        audio.update(&game, &events);
+        assert_eq!(vec![Sound::Turn], *played.borrow());
+
+        // the first ring closes after 5 seconds, with beeps 3, 2 and 1 seconds before
+        played.borrow_mut().clear();
+        game.set_ai(0, true);
+        game.set_ai(1, true);
+        for _ in 0..40 {
+            game.update(PLAYER_SPEED);
+            let events = game.take_events();
+            audio.update(&game, &events);
+        }
+        let beeps = played
+            .borrow()
+            .iter()
+            .filter(|&&s| s == Sound::Beep)
+            .count();
+        assert_eq!(3, beeps);
+
+        // muted games make no sound, and the end of the round is only announced once
+        audio.toggle_mute();
+        while !game.is_game_over() {
+            game.update(PLAYER_SPEED);
+            let events = game.take_events();
+            audio.update(&game, &events);
+        }
+        audio.toggle_mute();
+        played.borrow_mut().clear();
+        game.update(PLAYER_SPEED);
+        let events = game.take_events();
+        audio.update(&game, &events);
+        assert!(played.borrow().is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+//! This file contains the interface for bots: anything that steers a player in place of the
+//! built-in AI, such as a script written by a player (see `script.rs`).  Every time its player
+//! is about to move, a bot is given a read-only view of the board and answers with the
+//! direction it wants to move in.
+
+use crate::graphics::Block;
+use crate::player::Direction;
+
+/// Something that steers a player in place of the built-in AI.
+pub trait Bot {
+    /// Returns the direction the player should move in next, given a view of the board from
+    /// the player's point of view, or None to leave this move to the built-in AI.
+    fn choose(&mut self, board: &BoardView) -> Option<Direction>;
+}
+
+/// What a bot can see of a player taking part in the round.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerView {
+    pub head: Block,
+    pub direction: Direction,
+    pub crashed: bool,
+}
+
+/// A snapshot of the board as seen by the player a bot is steering, taken just before the
+/// player moves.  Changing it has no effect on the game.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BoardView {
+    width: u32,
+    height: u32,
+    me: usize,
+    players: Vec<PlayerView>,
+    food: Option<Block>,
+    free: Vec<bool>, // row by row, whether the player can move onto each Block
+}
+
+impl BoardView {
+    /// Creates a view of a board of the given size for the player with the given index.
+    /// `free` tells, row by row, whether that player can move onto each Block of the board
+    /// without crashing.
+    pub fn new(
+        width: u32,
+        height: u32,
+        me: usize,
+        players: Vec<PlayerView>,
+        food: Option<Block>,
+        free: Vec<bool>,
+    ) -> BoardView {
+        assert_eq!((width * height) as usize, free.len());
+        BoardView {
+            width,
+            height,
+            me,
+            players,
+            food,
+            free,
+        }
+    }
+
+    /// Returns the width of the board (in blocks).
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the board (in blocks).
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the index of the player being steered.
+    pub fn me(&self) -> usize {
+        self.me
+    }
+
+    /// Returns every player taking part in the round, in order, including the one being
+    /// steered.
+    pub fn players(&self) -> &[PlayerView] {
+        &self.players
+    }
+
+    /// Returns the position of the food item, if there is one on the board.
+    pub fn food(&self) -> Option<Block> {
+        self.food
+    }
+
+    /// Returns true if the player being steered can move onto the Block at the given
+    /// position without crashing.  Positions off the board are never free.
+    pub fn is_free(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+        self.free[y as usize * self.width as usize + x as usize]
+    }
+}
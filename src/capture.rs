@@ -0,0 +1,698 @@
+//! This file contains the capture of images of the game: screenshots, and recordings of a
+//! round as an animated GIF or a sequence of PNG images.  Frames are drawn by a software
+//! rasterizer rather than the GPU, so they can also be made without a window, straight from
+//! the state of a Game.
+
+use crate::effects::Effects;
+use crate::game::{Game, PLAYER_SPEED};
+use crate::hud;
+use crate::replay::{Replay, Step};
+use crate::theme::Theme;
+use piston_graphics::glyph_cache::rusttype::GlyphCache;
+use piston_graphics::types::Color;
+use piston_graphics::{Context, DrawState, Graphics, ImageSize, Transformed};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+use texture::{CreateTexture, Format, TextureOp, TextureSettings, UpdateTexture};
+
+/// The number of seconds of the game-over screen kept at the end of a recording.
+const END_OF_ROUND_HOLD: f64 = 2.0;
+
+/// The longest a recording can last, in seconds, so a round that never ends (e.g. between
+/// two AI players) can't fill up the disk.
+const MAX_RECORDING_TIME: f64 = 300.0;
+
+/// The scale at which recordings are drawn, relative to the size of the game in points.
+/// GIFs are drawn at half size to keep them small.
+const GIF_SCALE: f64 = 0.5;
+
+/// An RGBA image that the game can be drawn on through the Graphics trait.  Every pixel is
+/// opaque, and translucent colors are blended over what is already there.
+pub struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>, // 4 bytes per pixel, row by row from the top left
+}
+
+/// A texture of the software rasterizer, which is only used for the glyphs of text.
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// The glyph cache used to draw text on a Raster.
+pub type RasterGlyphs = GlyphCache<'static, (), Bitmap>;
+
+/// Returns a glyph cache for drawing text on a Raster, in the font embedded in the game.
+pub fn raster_glyphs() -> RasterGlyphs {
+    GlyphCache::from_bytes(crate::FONT, (), TextureSettings::new())
+        .expect("the embedded font is valid")
+}
+
+/// Draws a complete frame of the game (as it is shown in the window, without the settings
+/// menu) on a new Raster.  The frame is `scale` pixels per point.
+pub fn render(game: &Game, effects: &Effects, glyphs: &mut RasterGlyphs, scale: f64) -> Raster {
+    let [width, height] = hud::frame_size(game);
+    let mut raster = Raster::new(
+        (width * scale).round() as u32,
+        (height * scale).round() as u32,
+    );
+    let context = graphics_context(&raster).scale(scale, scale);
+    hud::draw_frame(game, effects, &context, &mut raster, glyphs);
+    raster
+}
+
+/// Returns a Context whose transform maps pixels of the given Raster to its whole area.
+fn graphics_context(raster: &Raster) -> Context {
+    Context::new_abs(raster.width as f64, raster.height as f64)
+}
+
+impl Raster {
+    /// Creates a black Raster of the given size in pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        for alpha in pixels.iter_mut().skip(3).step_by(4) {
+            *alpha = 255;
+        }
+        Raster {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns the color of the pixel at the given position as RGBA bytes.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    /// Saves the Raster as a PNG image.
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|err| err.to_string())
+    }
+
+    /// Blends the given color over the pixel at the given position.
+    fn blend(&mut self, x: usize, y: usize, color: Color) {
+        let index = (y * self.width as usize + x) * 4;
+        let alpha = color[3].clamp(0.0, 1.0);
+        for (pixel, value) in self.pixels[index..index + 3].iter_mut().zip(&color) {
+            let new = value.clamp(0.0, 1.0) * 255.0;
+            *pixel = (new * alpha + *pixel as f32 * (1.0 - alpha)).round() as u8;
+        }
+    }
+
+    /// Returns every pixel whose center lies inside at least one of the given triangles,
+    /// along with the index of the first such triangle and the barycentric coordinates of
+    /// the center within it.  Each pixel is returned once, so overlapping triangles of a
+    /// translucent shape are only blended once.  Vertices are given in normalized device
+    /// coordinates, from (-1, -1) at the bottom left to (1, 1) at the top right.
+    fn coverage(&self, triangles: &[[[f32; 2]; 3]]) -> Vec<(usize, usize, usize, [f64; 3])> {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let to_pixels = |[x, y]: [f32; 2]| {
+            [
+                (x as f64 + 1.0) / 2.0 * width,
+                (1.0 - y as f64) / 2.0 * height,
+            ]
+        };
+        let triangles: Vec<[[f64; 2]; 3]> = triangles
+            .iter()
+            .map(|triangle| triangle.map(to_pixels))
+            .collect();
+        // the range of pixels covered by the given points, as [left, right) and [top, bottom)
+        let bounds = |points: &[[f64; 2]]| {
+            let (mut left, mut top, mut right, mut bottom) = (width, height, 0.0_f64, 0.0_f64);
+            for point in points {
+                left = left.min(point[0].floor());
+                top = top.min(point[1].floor());
+                right = right.max(point[0].ceil());
+                bottom = bottom.max(point[1].ceil());
+            }
+            let clip = |value: f64, limit: f64| value.clamp(0.0, limit) as usize;
+            (
+                clip(left, width),
+                clip(top, height),
+                clip(right, width),
+                clip(bottom, height),
+            )
+        };
+        let all: Vec<[f64; 2]> = triangles.iter().flatten().copied().collect();
+        let (min_x, min_y, max_x, max_y) = bounds(&all);
+        if min_x >= max_x || min_y >= max_y {
+            return Vec::new();
+        }
+
+        let columns = max_x - min_x;
+        let mut covered = vec![false; columns * (max_y - min_y)];
+        let mut pixels = Vec::new();
+        let edge = |a: [f64; 2], b: [f64; 2], p: [f64; 2]| {
+            (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+        };
+        for (index, &[a, b, c]) in triangles.iter().enumerate() {
+            let area = edge(a, b, c);
+            if area == 0.0 {
+                continue;
+            }
+            let (left, top, right, bottom) = bounds(&[a, b, c]);
+            for y in top..bottom {
+                for x in left..right {
+                    let center = [x as f64 + 0.5, y as f64 + 0.5];
+                    let weights = [
+                        edge(b, c, center) / area,
+                        edge(c, a, center) / area,
+                        edge(a, b, center) / area,
+                    ];
+                    let mask = &mut covered[(y - min_y) * columns + (x - min_x)];
+                    if !*mask && weights.iter().all(|&weight| weight >= 0.0) {
+                        *mask = true;
+                        pixels.push((x, y, index, weights));
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
+
+/// Splits a list of vertices into triangles.
+fn triangles(vertices: &[[f32; 2]]) -> Vec<[[f32; 2]; 3]> {
+    vertices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect()
+}
+
+impl Graphics for Raster {
+    type Texture = Bitmap;
+
+    fn clear_color(&mut self, color: Color) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            for channel in 0..3 {
+                pixel[channel] = (color[channel].clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let mut all = Vec::new();
+        f(&mut |vertices| all.extend(triangles(vertices)));
+        for (x, y, _, _) in self.coverage(&all) {
+            self.blend(x, y, *color);
+        }
+    }
+
+    fn tri_list_c<F>(&mut self, _draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        // each triangle is filled in the color of its first vertex
+        let (mut all, mut colors) = (Vec::new(), Vec::new());
+        f(&mut |vertices, vertex_colors| {
+            all.extend(triangles(vertices));
+            colors.extend(vertex_colors.iter().step_by(3));
+        });
+        for (x, y, triangle, _) in self.coverage(&all) {
+            self.blend(x, y, colors[triangle]);
+        }
+    }
+
+    fn tri_list_uv<F>(&mut self, _: &DrawState, color: &[f32; 4], texture: &Bitmap, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        // textures are sampled at the nearest texel and tinted by the color
+        let (mut all, mut uvs) = (Vec::new(), Vec::new());
+        f(&mut |vertices, texture_coords| {
+            all.extend(triangles(vertices));
+            uvs.extend(triangles(texture_coords));
+        });
+        for (x, y, triangle, weights) in self.coverage(&all) {
+            let [a, b, c] = uvs[triangle];
+            let uv = |axis: usize| {
+                weights[0] * a[axis] as f64
+                    + weights[1] * b[axis] as f64
+                    + weights[2] * c[axis] as f64
+            };
+            let texel = texture.texel(uv(0), uv(1));
+            let mut tinted = *color;
+            for channel in 0..4 {
+                tinted[channel] *= texel[channel] as f32 / 255.0;
+            }
+            self.blend(x, y, tinted);
+        }
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Bitmap, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        // the game never draws textures with per-vertex colors, so they're drawn in white
+        self.tri_list_uv(draw_state, &[1.0; 4], texture, |receive| {
+            f(&mut |vertices, texture_coords, _| receive(vertices, texture_coords))
+        });
+    }
+}
+
+impl Bitmap {
+    /// Returns the texel at the given texture coordinates, which range from 0 to 1.
+    fn texel(&self, u: f64, v: f64) -> [u8; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0; 4];
+        }
+        let x = ((u * self.width as f64) as u32).min(self.width - 1);
+        let y = ((v * self.height as f64) as u32).min(self.height - 1);
+        let index = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+}
+
+impl ImageSize for Bitmap {
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl TextureOp<()> for Bitmap {
+    type Error = String;
+}
+
+impl CreateTexture<()> for Bitmap {
+    fn create<S: Into<[u32; 2]>>(
+        _factory: &mut (),
+        _format: Format,
+        memory: &[u8],
+        size: S,
+        _settings: &TextureSettings,
+    ) -> Result<Self, String> {
+        let [width, height] = size.into();
+        if memory.len() < (width * height * 4) as usize {
+            return Err(String::from("texture data is too short"));
+        }
+        Ok(Bitmap {
+            width,
+            height,
+            pixels: memory[..(width * height * 4) as usize].to_vec(),
+        })
+    }
+}
+
+impl UpdateTexture<()> for Bitmap {
+    fn update<O, S>(
+        &mut self,
+        _factory: &mut (),
+        _format: Format,
+        memory: &[u8],
+        offset: O,
+        size: S,
+    ) -> Result<(), String>
+    where
+        O: Into<[u32; 2]>,
+        S: Into<[u32; 2]>,
+    {
+        let ([x, y], [width, height]) = (offset.into(), size.into());
+        if x + width > self.width || y + height > self.height {
+            return Err(String::from("texture update is out of bounds"));
+        }
+        for row in 0..height {
+            let source = (row * width * 4) as usize;
+            let target = (((y + row) * self.width + x) * 4) as usize;
+            let length = (width * 4) as usize;
+            self.pixels[target..target + length].copy_from_slice(&memory[source..source + length]);
+        }
+        Ok(())
+    }
+}
+
+/// The formats a round can be recorded in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureFormat {
+    Gif,
+    Png, // a directory containing one PNG image per frame
+}
+
+impl CaptureFormat {
+    /// Returns the scale a recording in this format is drawn at.
+    fn scale(self) -> f64 {
+        match self {
+            CaptureFormat::Gif => GIF_SCALE,
+            CaptureFormat::Png => 1.0,
+        }
+    }
+}
+
+/// Returns the directory captures are saved in, `bloxide` inside the user's pictures
+/// directory, creating it if needed.
+pub fn capture_dir() -> Result<PathBuf, String> {
+    let dir = dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .ok_or("no pictures directory on this platform")?
+        .join("bloxide");
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    Ok(dir)
+}
+
+/// Returns a new path in the capture directory with the given extension, named after the
+/// current time.
+pub fn new_capture_path(extension: &str) -> Result<PathBuf, String> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    let name = format!("bloxide-{}", seconds);
+    Ok(unused_path(&capture_dir()?, &name, extension))
+}
+
+/// Returns the path of the file with the given name and extension in the given directory,
+/// or, if there already is one, of the first of `name-2`, `name-3` and so on that there
+/// isn't, so captures taken within the same second don't replace each other.
+fn unused_path(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    let mut path = dir.join(name).with_extension(extension);
+    let mut count = 1;
+    while path.exists() {
+        count += 1;
+        path = dir
+            .join(format!("{}-{}", name, count))
+            .with_extension(extension);
+    }
+    path
+}
+
+/// Saves the current frame of the game as a PNG image in the capture directory, and returns
+/// where it was saved.
+pub fn screenshot(
+    game: &Game,
+    effects: &Effects,
+    glyphs: &mut RasterGlyphs,
+) -> Result<PathBuf, String> {
+    let path = new_capture_path("png")?;
+    render(game, effects, glyphs, 1.0).save_png(&path)?;
+    Ok(path)
+}
+
+/// A Recording writes frames to a GIF or a directory of PNG images.  Frames are encoded on
+/// a separate thread, so recording doesn't slow down the game.
+pub struct Recording {
+    path: PathBuf,
+    frames: Option<Sender<Raster>>,
+    writer: Option<JoinHandle<Result<(), String>>>,
+}
+
+impl Recording {
+    /// Starts a recording in the given format at the given path, which is a file for a GIF
+    /// and a directory for a PNG sequence.  Each frame is shown for one tick of the game.
+    pub fn start(format: CaptureFormat, path: PathBuf) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel::<Raster>();
+        let target = path.clone();
+        let writer = match format {
+            CaptureFormat::Gif => {
+                let file = File::create(&target).map_err(|err| err.to_string())?;
+                thread::spawn(move || write_gif(BufWriter::new(file), receiver.iter()))
+            }
+            CaptureFormat::Png => {
+                fs::create_dir_all(&target).map_err(|err| err.to_string())?;
+                thread::spawn(move || {
+                    for (index, frame) in receiver.iter().enumerate() {
+                        frame.save_png(&target.join(format!("frame-{:05}.png", index)))?;
+                    }
+                    Ok(())
+                })
+            }
+        };
+        Ok(Recording {
+            path,
+            frames: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    /// Adds a frame to the end of the recording.
+    pub fn add_frame(&mut self, frame: Raster) {
+        if let Some(frames) = &self.frames {
+            // if the writer has stopped, its error is reported by finish
+            let _ = frames.send(frame);
+        }
+    }
+
+    /// Waits for every frame to be written and returns where the recording was saved.
+    pub fn finish(mut self) -> Result<PathBuf, String> {
+        self.frames = None;
+        match self.writer.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result.map(|_| self.path.clone()),
+            _ => Err(String::from("the recording thread panicked")),
+        }
+    }
+}
+
+/// Writes the given frames as an animated GIF which loops forever, showing each frame for
+/// one tick of the game.
+fn write_gif<W, I>(writer: W, frames: I) -> Result<(), String>
+where
+    W: std::io::Write,
+    I: Iterator<Item = Raster>,
+{
+    let mut frames = frames.peekable();
+    let (width, height) = match frames.peek() {
+        Some(frame) => (frame.width as u16, frame.height as u16),
+        None => return Ok(()),
+    };
+    let mut encoder =
+        gif::Encoder::new(writer, width, height, &[]).map_err(|err| err.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|err| err.to_string())?;
+    for mut raster in frames {
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut raster.pixels, 10);
+        frame.delay = (PLAYER_SPEED * 100.0).round() as u16;
+        encoder.write_frame(&frame).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// A Recorder records the round being played, one frame per tick, until it is stopped or
+/// shortly after the round ends.
+pub struct Recorder {
+    recording: Option<Recording>,
+    scale: f64,
+    time: f64,             // seconds since the recording started
+    next_frame: f64,       // the time at which the next frame is due
+    ended_at: Option<f64>, // the time at which the round ended, if it has
+}
+
+impl Recorder {
+    /// Starts recording in the given format at the given path.  The first frame is taken by
+    /// the next call to update.
+    pub fn start(format: CaptureFormat, path: PathBuf) -> Result<Self, String> {
+        Ok(Recorder {
+            recording: Some(Recording::start(format, path)?),
+            scale: format.scale(),
+            time: 0.0,
+            next_frame: 0.0,
+            ended_at: None,
+        })
+    }
+
+    /// Starts recording in the given format to a new path in the capture directory.
+    pub fn start_new(format: CaptureFormat) -> Result<Self, String> {
+        let extension = match format {
+            CaptureFormat::Gif => "gif",
+            CaptureFormat::Png => "",
+        };
+        Recorder::start(format, new_capture_path(extension)?)
+    }
+
+    /// Advances the recording by the given number of seconds, taking a frame of the game
+    /// whenever one is due.  Returns true once the recording is complete, which is a little
+    /// while after the round ends, after which the recording should be finished.
+    pub fn update(
+        &mut self,
+        game: &Game,
+        effects: &Effects,
+        glyphs: &mut RasterGlyphs,
+        time_elapsed: f64,
+    ) -> bool {
+        if game.is_game_over() && self.ended_at.is_none() {
+            self.ended_at = Some(self.time);
+        }
+        while self.time >= self.next_frame {
+            let frame = render(game, effects, glyphs, self.scale);
+            if let Some(recording) = &mut self.recording {
+                recording.add_frame(frame);
+            }
+            self.next_frame += PLAYER_SPEED;
+        }
+        self.time += time_elapsed;
+        let held = self
+            .ended_at
+            .is_some_and(|end| self.time - end >= END_OF_ROUND_HOLD);
+        held || self.time >= MAX_RECORDING_TIME
+    }
+
+    /// Stops recording, waits for every frame to be written and returns where the recording
+    /// was saved.
+    pub fn finish(mut self) -> Result<PathBuf, String> {
+        match self.recording.take() {
+            Some(recording) => recording.finish(),
+            None => Err(String::from("nothing was recorded")),
+        }
+    }
+}
+
+/// Plays a round with every player controlled by the AI without drawing it on the screen,
+/// and records it in the given format at the given path.  The countdown before the round is
+/// skipped, since nobody needs to get ready.
+pub fn export_ai_round(
+    mut game: Game,
+    format: CaptureFormat,
+    path: PathBuf,
+) -> Result<PathBuf, String> {
+    let mut effects = Effects::new();
+    let mut glyphs = raster_glyphs();
+    let mut recorder = Recorder::start(format, path)?;
+    for index in 0..game.players().len() {
+        game.set_ai(index, true);
+    }
+    game.skip_countdown();
+    while !recorder.update(&game, &effects, &mut glyphs, PLAYER_SPEED) {
+        game.update(PLAYER_SPEED);
+        let events = game.take_events();
+        effects.update(&game, &events, PLAYER_SPEED);
+    }
+    recorder.finish()
+}
+
+/// Plays back the given replay without drawing it on the screen, and records the round in
+/// the given theme and format at the given path.  The recording goes on for a little while
+/// after the round ends, as it does when recording live.
+pub fn export_replay(
+    replay: &Replay,
+    theme: Theme,
+    format: CaptureFormat,
+    path: PathBuf,
+) -> Result<PathBuf, String> {
+    let mut game = replay.game()?;
+    game.set_theme(theme);
+    let mut effects = Effects::new();
+    let mut glyphs = raster_glyphs();
+    let mut recorder = Recorder::start(format, path)?;
+    let mut steps = replay.steps().iter();
+    loop {
+        let time_elapsed = match steps.next() {
+            Some(&Step::Key(key)) => {
+                game.key_pressed(key);
+                continue;
+            }
+            Some(&Step::Update(time_elapsed)) => time_elapsed,
+            None => PLAYER_SPEED,
+        };
+        if recorder.update(&game, &effects, &mut glyphs, time_elapsed) {
+            break;
+        }
+        game.update(time_elapsed);
+        let events = game.take_events();
+        effects.update(&game, &events, time_elapsed);
+    }
+    recorder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Block;
+    use input::Key;
+
+    #[test]
+    fn test_raster() {
+        let mut raster = Raster::new(10, 10);
+        let context = graphics_context(&raster);
+        piston_graphics::rectangle(
+            [1.0, 0.0, 0.0, 1.0],
+            [2.0, 2.0, 4.0, 4.0],
+            context.transform,
+            &mut raster,
+        );
+        piston_graphics::rectangle(
+            [0.0, 0.0, 1.0, 0.5],
+            [0.0, 0.0, 10.0, 3.0],
+            context.transform,
+            &mut raster,
+        );
+        assert_eq!([255, 0, 0, 255], raster.pixel(5, 5));
+        assert_eq!([0, 0, 0, 255], raster.pixel(6, 6));
+        assert_eq!([128, 0, 128, 255], raster.pixel(2, 2));
+        assert_eq!([0, 0, 128, 255], raster.pixel(9, 0));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut game = Game::new(35, 25);
+        game.skip_countdown();
+        let mut glyphs = raster_glyphs();
+        let raster = render(&game, &Effects::new(), &mut glyphs, 0.5);
+        let [width, height] = hud::frame_size(&game);
+        assert_eq!((width / 2.0).round() as u32, raster.width);
+        assert_eq!((height / 2.0).round() as u32, raster.height);
+        // the middle of the board is the background, and player 1's trail is in its color
+        let background = game.theme().background.map(|c| (c * 255.0).round() as u8);
+        assert_eq!(background, raster.pixel(raster.width / 2, 150));
+        let red = game
+            .team_of_player(0)
+            .color
+            .map(|c| (c * 255.0).round() as u8);
+        assert_eq!(red, raster.pixel(3 * 25 / 2 + 6, 3 * 25 / 2 + 6));
+
+        // every segment of a trail that has turned is drawn, not just the oldest
+        game.key_pressed(Key::S);
+        for _ in 0..4 {
+            game.update(PLAYER_SPEED);
+        }
+        assert_eq!(Block { x: 5, y: 6 }, game.players()[0].head());
+        let raster = render(&game, &Effects::new(), &mut glyphs, 0.5);
+        assert_eq!(red, raster.pixel(5 * 25 / 2 + 6, 4 * 25 / 2 + 6));
+    }
+
+    #[test]
+    fn test_unused_path() {
+        let dir = std::env::temp_dir().join(format!("bloxide-captures-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = unused_path(&dir, "bloxide-1", "png");
+        assert_eq!(dir.join("bloxide-1.png"), first);
+        fs::write(&first, "").unwrap();
+        let second = unused_path(&dir, "bloxide-1", "png");
+        assert_eq!(dir.join("bloxide-1-2.png"), second);
+        fs::write(&second, "").unwrap();
+        assert_eq!(
+            dir.join("bloxide-1-3.png"),
+            unused_path(&dir, "bloxide-1", "png")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
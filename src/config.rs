@@ -0,0 +1,215 @@
+//! This file contains the config file, which remembers the chosen theme and settings between
+//! runs of the game and can describe a custom theme.
+
+use crate::audio::AudioSettings;
+use crate::capture::CaptureFormat;
+use crate::profile::{Profile, ProfileSpec};
+use crate::settings::{Settings, MAX_PLAYERS};
+use crate::theme::{Theme, ThemeSpec};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// The contents of the config file.  Any field that is left out of the file takes its
+/// default value, so an empty file gives the classic theme and rules.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub theme: String, // the name of the selected theme
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_theme: Option<ThemeSpec>,
+    pub settings: Settings,
+    pub capture_format: CaptureFormat, // the format rounds are recorded in
+    pub audio: AudioSettings,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<ProfileSpec>,
+    // the name of the profile each player plays as, in player order, with "" for a guest
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub seats: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            theme: Theme::classic().name,
+            custom_theme: None,
+            settings: Settings::default(),
+            capture_format: CaptureFormat::Gif,
+            audio: AudioSettings::default(),
+            profiles: Vec::new(),
+            seats: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the location of the config file, `bloxide/config.toml` inside the user's
+    /// config directory (e.g. `~/.config` on Linux), if the platform has one.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("bloxide").join("config.toml"))
+    }
+
+    /// Loads the config file.  If there is no config file the default config is returned,
+    /// and if the file can't be read or parsed the error is reported and the default config
+    /// is returned, so a broken config file never stops the game from starting.
+    pub fn load() -> Config {
+        let path = match Config::path() {
+            Some(path) if path.exists() => path,
+            _ => return Config::default(),
+        };
+        let parsed = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Config::parse(&text));
+        parsed.unwrap_or_else(|err| {
+            eprintln!("Ignoring config file {}: {}", path.display(), err);
+            Config::default()
+        })
+    }
+
+    /// Writes this config to the config file, creating its directory if needed.
+    pub fn save(&self) -> Result<(), String> {
+        let path = Config::path().ok_or("no config directory on this platform")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, self.to_toml()?).map_err(|err| err.to_string())
+    }
+
+    /// Parses a config from the contents of a config file.  Settings outside the limits the
+    /// game supports are brought back within them.
+    fn parse(text: &str) -> Result<Config, String> {
+        let mut config: Config = toml::from_str(text).map_err(|err| err.to_string())?;
+        config.settings.clamp_to_limits();
+        Ok(config)
+    }
+
+    /// Returns the contents of a config file describing this config.
+    fn to_toml(&self) -> Result<String, String> {
+        // Going through a Value puts plain values before tables, which TOML requires.
+        toml::Value::try_from(self)
+            .map(|value| value.to_string())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Returns every theme that can be selected: the built-in themes, followed by the
+    /// custom theme if the config file describes a valid one.  An invalid custom theme is
+    /// reported and left out.
+    pub fn themes(&self) -> Vec<Theme> {
+        let mut themes = Theme::built_in();
+        if let Some(spec) = &self.custom_theme {
+            match spec.to_theme() {
+                Ok(theme) => themes.push(theme),
+                Err(err) => eprintln!("Ignoring custom theme: {}", err),
+            }
+        }
+        themes
+    }
+
+    /// Returns every profile described by the config file.  Invalid profiles, and profiles
+    /// with the same name as an earlier one, are reported and left out.
+    pub fn profiles(&self) -> Vec<Profile> {
+        let mut profiles: Vec<Profile> = Vec::new();
+        for spec in &self.profiles {
+            match spec.to_profile() {
+                Ok(profile) if profiles.iter().any(|other| other.name == profile.name) => {
+                    eprintln!("Ignoring second profile named \"{}\"", profile.name)
+                }
+                Ok(profile) => profiles.push(profile),
+                Err(err) => eprintln!("Ignoring profile: {}", err),
+            }
+        }
+        profiles
+    }
+
+    /// Returns the index in `profiles` of the profile each player plays as, in player order,
+    /// or None for a guest or a profile that doesn't exist.
+    pub fn seated_profiles(&self, profiles: &[Profile]) -> [Option<usize>; MAX_PLAYERS] {
+        let mut seated = [None; MAX_PLAYERS];
+        for (seat, name) in seated.iter_mut().zip(&self.seats) {
+            *seat = profiles.iter().position(|profile| &profile.name == name);
+        }
+        seated
+    }
+
+    /// Returns the index in `themes` of the selected theme, or of the classic theme if the
+    /// selected theme doesn't exist.
+    pub fn selected_theme(&self, themes: &[Theme]) -> usize {
+        themes
+            .iter()
+            .position(|theme| theme.name == self.theme)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Controls;
+    use crate::settings::TrailMode;
+
+    #[test]
+    fn test_round_trip() {
+        let mut config = Config {
+            theme: String::from("Mine"),
+            custom_theme: Some(ThemeSpec {
+                name: Some(String::from("Mine")),
+                food: Some(String::from("00ff00")),
+                ..ThemeSpec::default()
+            }),
+            ..Config::default()
+        };
+
+        config.settings.trail_mode = TrailMode::DEFAULT_SNAKE;
+        config.settings.glow = true;
+        config.capture_format = CaptureFormat::Png;
+        config.audio.music = true;
+        config.profiles = vec![ProfileSpec {
+            name: String::from("Ada"),
+            color: Some(String::from("00ff00")),
+            controls: Some(Controls::Ijkl),
+        }];
+        config.seats = vec![String::new(), String::from("Ada")];
+        let parsed = Config::parse(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(config, parsed);
+
+        let themes = parsed.themes();
+        assert_eq!(Theme::built_in().len() + 1, themes.len());
+        assert_eq!("Mine", themes[parsed.selected_theme(&themes)].name);
+
+        let profiles = parsed.profiles();
+        assert_eq!(
+            [None, Some(0), None, None],
+            parsed.seated_profiles(&profiles)
+        );
+    }
+
+    #[test]
+    fn test_partial_config() {
+        let config = Config::parse("theme = \"Protanopia\"\n[settings]\nplayer_count = 3\n");
+        let config = config.unwrap();
+        assert_eq!(3, config.settings.player_count);
+        assert!(config.settings.smooth_movement);
+        let themes = config.themes();
+        assert_eq!("Protanopia", themes[config.selected_theme(&themes)].name);
+        assert_eq!(Config::default(), Config::parse("").unwrap());
+    }
+
+    #[test]
+    fn test_player_count_limits() {
+        let config = Config::parse("[settings]\nplayer_count = 6\n").unwrap();
+        assert_eq!(MAX_PLAYERS, config.settings.player_count);
+        let config = Config::parse("[settings]\nplayer_count = 0\n").unwrap();
+        assert_eq!(2, config.settings.player_count);
+    }
+
+    #[test]
+    fn test_capture_format() {
+        // as suggested in the README
+        let config = Config::parse("capture_format = \"png\"\n").unwrap();
+        assert_eq!(CaptureFormat::Png, config.capture_format);
+        assert!(Config::default()
+            .to_toml()
+            .unwrap()
+            .contains("capture_format = \"gif\""));
+    }
+}
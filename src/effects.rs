@@ -0,0 +1,113 @@
+//! This file contains purely visual effects, such as the burst of particles shown where a
+//! player crashes.  Effects only react to the events of the Game and never change it, so they
+//! have no influence on how a round plays out.
+
+use crate::event::GameEvent;
+use crate::game::Game;
+use crate::graphics::{Block, POINTS_PER_BLOCK};
+use piston_graphics::types::Color;
+use piston_graphics::{rectangle, Context, Graphics};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The number of particles in the burst shown when a player crashes.
+const PARTICLES_PER_CRASH: usize = 40;
+
+/// The number of seconds a particle lasts before disappearing.
+const PARTICLE_LIFETIME: f64 = 0.8;
+
+/// The fastest a particle can be moving when it is created, in Blocks per second.
+const PARTICLE_SPEED: f64 = 8.0;
+
+/// The width and height of a particle, in Blocks.
+const PARTICLE_SIZE: f64 = 0.25;
+
+/// A single particle of an explosion.  Its position and velocity are measured in Blocks and
+/// Blocks per second, so they don't depend on the size of the window.
+struct Particle {
+    position: [f64; 2],
+    velocity: [f64; 2],
+    age: f64, // the number of seconds since the particle was created
+    color: Color,
+}
+
+/// Effects keeps track of the particles currently on the screen.
+#[derive(Default)]
+pub struct Effects {
+    particles: Vec<Particle>,
+}
+
+impl Effects {
+    /// Creates a new Effects object with nothing on the screen.
+    pub fn new() -> Self {
+        Effects {
+            particles: Vec::new(),
+        }
+    }
+
+    /// Advances all effects by the given amount of time and starts an explosion for every
+    /// crash among the given events, which the Game reported since the last update.
+    pub fn update(&mut self, game: &Game, events: &[GameEvent], time_elapsed: f64) {
+        for event in events {
+            if let GameEvent::Crashed { player, cell, .. } = *event {
+                // the sparks are random, but drawn from the game's seed, the time and the
+                // player rather than the game's own generator, so they look the same every
+                // time a seeded game is played without changing how it plays out
+                let seed = game.seed() ^ ((game.ticks() as u64) << 8) ^ player as u64;
+                self.explode(cell, game.team_of_player(player).color, seed);
+            }
+        }
+
+        for particle in &mut self.particles {
+            particle.age += time_elapsed;
+            particle.position[0] += particle.velocity[0] * time_elapsed;
+            particle.position[1] += particle.velocity[1] * time_elapsed;
+            // particles slow down as they spread out
+            particle.velocity[0] *= 1.0 - (3.0 * time_elapsed).min(1.0);
+            particle.velocity[1] *= 1.0 - (3.0 * time_elapsed).min(1.0);
+        }
+        self.particles
+            .retain(|particle| particle.age < PARTICLE_LIFETIME);
+    }
+
+    /// Adds a burst of particles flying outward from the center of the given Block, scattered
+    /// at random from the given seed.  Each particle is either the given color or white, so
+    /// the burst looks like sparks.
+    fn explode(&mut self, site: Block, color: Color, seed: u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for _ in 0..PARTICLES_PER_CRASH {
+            let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+            let speed = rng.gen_range(0.2..1.0) * PARTICLE_SPEED;
+            self.particles.push(Particle {
+                position: [site.x as f64 + 0.5, site.y as f64 + 0.5],
+                velocity: [angle.cos() * speed, angle.sin() * speed],
+                age: 0.0,
+                color: if rng.gen_bool(0.3) {
+                    [1.0, 1.0, 1.0, 1.0]
+                } else {
+                    color
+                },
+            });
+        }
+    }
+
+    /// Draws all particles, fading each one out over its lifetime.
+    pub fn draw<G: Graphics>(&self, con: &Context, g: &mut G) {
+        for particle in &self.particles {
+            let mut color = particle.color;
+            color[3] *= (1.0 - particle.age / PARTICLE_LIFETIME) as f32;
+            let [x, y] = particle.position;
+            rectangle(
+                color,
+                [
+                    (x - PARTICLE_SIZE / 2.0) * POINTS_PER_BLOCK,
+                    (y - PARTICLE_SIZE / 2.0) * POINTS_PER_BLOCK,
+                    PARTICLE_SIZE * POINTS_PER_BLOCK,
+                    PARTICLE_SIZE * POINTS_PER_BLOCK,
+                ],
+                con.transform,
+                g,
+            );
+        }
+    }
+}
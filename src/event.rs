@@ -0,0 +1,40 @@
+//! This file contains the events a Game reports as a round plays out.  Anything that reacts
+//! to the game, such as the visual effects, the sounds and the match history, reads these
+//! events instead of comparing the state of the game from one frame to the next.
+
+use crate::graphics::Block;
+use crate::player::Direction;
+use serde::{Deserialize, Serialize};
+
+/// What a player ran into.  Other players are given by their index.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionKind {
+    Wall,                 // the edge of the board, or a ring closed in sudden death
+    SelfTrail,            // the player's own trail
+    OpponentTrail(usize), // the trail of another player, which may be a teammate
+    HeadOn(usize),        // the head of another player, on or moving onto the same Block
+    Obstacle,             // an obstacle on the board, on maps that have them
+}
+
+/// Something that happened in a Game.  Players are given by their index, and teams by the
+/// index used with `Game::team`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameEvent {
+    /// A new round has started.
+    RoundStarted,
+    /// A player has moved forward one Block, onto `to`.
+    Moved { player: usize, to: Block },
+    /// A player has started moving in a new direction.
+    Turned { player: usize, direction: Direction },
+    /// A player has crashed into `cell`.
+    Crashed {
+        player: usize,
+        cause: CollisionKind,
+        cell: Block,
+    },
+    /// The round is over, and was won by the team with the given index, or was a draw.
+    RoundOver { winner: Option<usize> },
+    /// The AI has been switched on or off for a player.
+    AiToggled { player: usize, ai: bool },
+}
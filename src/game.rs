@@ -1,59 +1,151 @@
+use crate::arena;
+use crate::bot::{BoardView, Bot, PlayerView};
+use crate::event::{CollisionKind, GameEvent};
 use crate::graphics::{draw_rectangle, Block};
-use crate::player;
-use crate::player::{Direction, Player};
-use piston_window::{Context, G2d, Key};
+use crate::player::Player;
+use crate::profile::{assign_controls, Controls, Profile};
+use crate::settings::{ArenaStyle, Settings, SuddenDeath, TeamMode, TrailMode, MAX_PLAYERS};
+use crate::spawn;
+use crate::team;
+use crate::team::Team;
+use crate::theme::Theme;
+use input::Key;
+use piston_graphics::{Context, Graphics};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 /// Determines the time step length in between advancements of each player.
 pub const PLAYER_SPEED: f64 = 0.10;
 
+/// The dimensions of the game board every frontend plays on, in "block" units.
+pub const BOARD_WIDTH: u32 = 35;
+pub const BOARD_HEIGHT: u32 = 25;
+
+/// The largest number of events kept waiting to be taken.  If nothing takes them, the oldest
+/// are dropped, so a Game nobody listens to doesn't keep growing.
+const MAX_EVENTS: usize = 4096;
+
 /// A Game struct holds information related to the size of the game board,
-/// the two players, and the status of the game.
+/// the players, and the status of the game.  A Game can be serialized to save a round in
+/// progress (see `save.rs`); the theme is left out, since it only changes how the game looks.
+#[derive(Serialize, Deserialize)]
 pub struct Game {
     width: u32,  // Measured in "blocks"
     height: u32, // Measured in "blocks"
 
-    ai: bool, // whether or not the AI is playing for player one
-
-    player_one: player::Player,
-    player_two: player::Player,
+    players: Vec<Player>, // players[0] is player 1, and so on
 
-    winner: Option<bool>, // Some(true) for player 1, Some(false) for player 2, None if game isn't over.
+    winner: Option<usize>, // the winning team's index, None while playing or after a draw
 
     is_game_over: bool,
+
+    settings: Settings,
+    #[serde(skip, default = "Theme::classic")]
+    theme: Theme,
+    food: Option<Block>, // the location of the food item in snake mode, if there is one
+    #[serde(default)]
+    obstacles: Vec<Block>, // the Blocks of the arena that nobody can move onto
+
+    #[serde(default)]
+    countdown: f64, // time left before the players start moving, in seconds
+    round_time: f64,   // time elapsed since the start of the round, in seconds
+    rings_closed: u32, // the number of rings of the board taken over by the wall in sudden death
+
+    scores: [u32; MAX_PLAYERS], // the number of rounds won by each team
+
+    #[serde(default)]
+    seed: u64, // the seed the game's random numbers were first drawn from
+    #[serde(default = "ChaCha8Rng::from_entropy")]
+    rng: ChaCha8Rng, // where every random choice in the game, like spawns and food, comes from
+
+    profiles: [Option<Profile>; MAX_PLAYERS], // the profile each player is playing as, if any
+    controls: [Controls; MAX_PLAYERS],        // the keys each player is steered with
+    #[serde(skip)]
+    bots: [Option<Box<dyn Bot>>; MAX_PLAYERS], // what steers each player instead of the built-in AI
+
+    #[serde(skip)]
+    events: VecDeque<GameEvent>, // what has happened since the events were last taken
 }
 
 impl Game {
-    /// Creates a new Game with the given width and height.  Initialize Players 1 and 2 for the
-    /// game by calling the player_1 and player_2 methods in player.rs.
+    /// Creates a new Game with the given width and height using the classic rules.
     pub fn new(width: u32, height: u32) -> Self {
-        Game {
+        Game::with_settings(width, height, Settings::default())
+    }
+
+    /// Creates a new Game with the given width, height and Settings, drawing its random
+    /// numbers from a random seed.
+    pub fn with_settings(width: u32, height: u32, settings: Settings) -> Self {
+        // random seeds are kept short, so they are easy to read off the screen and type in
+        let seed = rand::thread_rng().gen::<u32>() as u64;
+        Game::with_seed(width, height, settings, seed)
+    }
+
+    /// Creates a new Game with the given width, height and Settings, drawing its random
+    /// numbers from the given seed.  Games created from the same seed and played the same
+    /// way play out exactly the same.  The players are created by the spawn_players method.
+    pub fn with_seed(width: u32, height: u32, settings: Settings, seed: u64) -> Self {
+        let mut game = Game {
             width,
             height,
 
-            ai: false,
-
-            player_one: Player::player_1(),
-            player_two: Player::player_2(width, height),
+            players: Vec::new(),
 
             winner: None,
 
             is_game_over: false,
-        }
+
+            settings,
+            theme: Theme::classic(),
+            food: None,
+            obstacles: Vec::new(),
+
+            countdown: settings.countdown as f64,
+            round_time: 0.0,
+            rings_closed: 0,
+
+            scores: [0; MAX_PLAYERS],
+
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+
+            profiles: Default::default(),
+            controls: Controls::ALL,
+            bots: Default::default(),
+
+            events: VecDeque::new(),
+        };
+        game.spawn_players();
+        game
     }
 
-    /// Draws the game by first drawing both players, and then drawing a black border on the outer
-    /// edge of the game window.
-    pub fn draw(&self, con: &Context, g: &mut G2d) -> (Option<bool>, bool) {
-        self.player_one.draw(con, g, self.ai);
-        self.player_two.draw(con, g, false);
+    /// Draws the game by first drawing the obstacles, the food item and the players, and then
+    /// drawing a border on the outer edge of the game window, all in the colors of the game's
+    /// theme.  Obstacles are drawn in the color of the border.
+    pub fn draw<G: Graphics>(&self, con: &Context, g: &mut G) {
+        for obstacle in &self.obstacles {
+            obstacle.draw(self.theme.border, con, g);
+        }
+        if let Some(food) = self.food {
+            food.draw(self.theme.food, con, g);
+        }
 
-        // draw the border of the game last so that it covers up anything on the border
-        draw_rectangle([0., 0., 0., 1.0], 0, 0, self.width, 1, con, g);
-        draw_rectangle([0., 0., 0., 1.0], 0, 0, 1, self.height, con, g);
-        draw_rectangle([0., 0., 0., 1.0], 0, self.height - 1, self.width, 1, con, g);
-        draw_rectangle([0., 0., 0., 1.0], self.width - 1, 0, 1, self.height, con, g);
+        let smooth = self.settings.smooth_movement && !self.is_game_over;
+        for (index, player) in self.players.iter().enumerate() {
+            let color = self.team_of_player(index).color;
+            player.draw(color, con, g, smooth, self.settings.glow);
+        }
 
-        (self.winner, self.ai)
+        // draw the border of the game last so that it covers up anything on the border,
+        // including any rings that have been closed in sudden death
+        let border = self.border_width();
+        let color = self.theme.border;
+        draw_rectangle(color, 0, 0, self.width, border, con, g);
+        draw_rectangle(color, 0, 0, border, self.height, con, g);
+        draw_rectangle(color, 0, self.height - border, self.width, border, con, g);
+        draw_rectangle(color, self.width - border, 0, border, self.height, con, g);
     }
 
     /// Returns the height of the game (in blocks)
@@ -66,181 +158,1215 @@ impl Game {
         self.height
     }
 
+    /// Returns the width of the wall around the edge of the board (in blocks), which grows
+    /// as rings close in sudden death.
+    pub fn border_width(&self) -> u32 {
+        1 + self.rings_closed
+    }
+
+    /// Returns the number of rings of the board that have been closed in sudden death.
+    pub fn rings_closed(&self) -> u32 {
+        self.rings_closed
+    }
+
+    /// Returns the Blocks covered by the obstacles of the arena, if there are any.
+    pub fn obstacles(&self) -> &[Block] {
+        &self.obstacles
+    }
+
+    /// Returns the position of the food item, if there is one on the board.
+    pub fn food(&self) -> Option<Block> {
+        self.food
+    }
+
+    /// Returns true if the round is over.
+    pub fn is_game_over(&self) -> bool {
+        self.is_game_over
+    }
+
+    /// Returns the players taking part in the round, in order.
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// Returns the number of teams taking part in the round.
+    pub fn team_count(&self) -> usize {
+        match self.settings.team_mode {
+            TeamMode::FreeForAll => self.players.len(),
+            TeamMode::Teams { .. } => self.players.len().min(2),
+        }
+    }
+
+    /// Returns the Team with the given index, named and colored according to the game's
+    /// theme.  In free-for-all mode, player 1 is reported as the AI player (the green player
+    /// in the classic theme) while it is being controlled by the AI, and a player with a
+    /// profile is named after it and drawn in its color, if it has one.
+    pub fn team(&self, index: usize) -> Team {
+        if self.settings.team_mode != TeamMode::FreeForAll {
+            return team::team(index, self.settings.team_mode, &self.theme);
+        }
+        match &self.profiles[index] {
+            _ if self.players[index].is_ai() => team::ai_player(&self.theme),
+            Some(profile) => Team {
+                name: profile.name.clone(),
+                color: profile.color.unwrap_or(self.theme.players[index]),
+            },
+            None => team::team(index, self.settings.team_mode, &self.theme),
+        }
+    }
+
+    /// Returns the Team that the player with the given index plays for.
+    pub fn team_of_player(&self, index: usize) -> Team {
+        self.team(team::team_of(index, self.settings.team_mode))
+    }
+
+    /// Returns the number of rounds won by the team with the given index since the number of
+    /// players or the team mode was last changed.
+    pub fn score(&self, team: usize) -> u32 {
+        self.scores[team]
+    }
+
+    /// Returns the Team that won the round, or None if the round isn't over or ended in a
+    /// draw.
+    pub fn winner(&self) -> Option<Team> {
+        self.winner.map(|index| self.team(index))
+    }
+
+    /// Returns the index of the team that won the round, or None if the round isn't over or
+    /// ended in a draw.
+    pub fn winner_index(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Returns a short description of the board the game is played on, e.g. "Open 35x25" or
+    /// "Maze 35x25".
+    pub fn map_name(&self) -> String {
+        let style = if self.obstacles.is_empty() {
+            ArenaStyle::Open
+        } else {
+            self.settings.arena
+        };
+        format!("{} {}x{}", style.name(), self.width, self.height)
+    }
+
+    /// Returns the message announcing the result of the round, e.g. "Red Player Wins!", or
+    /// "Draw!" if no team is left.
+    pub fn result_message(&self) -> String {
+        match self.winner() {
+            Some(team) => format!("{} Wins!", team.name),
+            None => String::from("Draw!"),
+        }
+    }
+
+    /// Returns a sentence describing what the player with the given index crashed into, e.g.
+    /// "Blue Player crashed into Red Player's trail", or None if it hasn't crashed.
+    pub fn crash_message(&self, index: usize) -> Option<String> {
+        let name = |index| match self.settings.team_mode {
+            TeamMode::FreeForAll => self.team(index).name,
+            TeamMode::Teams { .. } => self.player_name(index),
+        };
+        let what = match self.players[index].crash_kind()? {
+            CollisionKind::Wall => String::from("the wall"),
+            CollisionKind::SelfTrail => String::from("their own trail"),
+            CollisionKind::OpponentTrail(other) => format!("{}'s trail", name(other)),
+            CollisionKind::HeadOn(other) => format!("{} head-on", name(other)),
+            CollisionKind::Obstacle => String::from("an obstacle"),
+        };
+        Some(format!("{} crashed into {}", name(index), what))
+    }
+
+    /// Returns the profile the player with the given index is playing as, if any.
+    pub fn profile(&self, index: usize) -> Option<&Profile> {
+        self.profiles[index].as_ref()
+    }
+
+    /// Sets the profile the player with the given index plays as, or None for a guest, and
+    /// works out again which keys steer each player.
+    pub fn set_profile(&mut self, index: usize, profile: Option<Profile>) {
+        self.profiles[index] = profile;
+        let preferences = self
+            .profiles
+            .each_ref()
+            .map(|profile| profile.as_ref().and_then(|profile| profile.controls));
+        self.controls = assign_controls(preferences);
+    }
+
+    /// Returns the keys the player with the given index is steered with.
+    pub fn controls(&self, index: usize) -> Controls {
+        self.controls[index]
+    }
+
+    /// Returns the name of the player with the given index: the name of its profile, or
+    /// e.g. "Player 1" for a guest.
+    pub fn player_name(&self, index: usize) -> String {
+        match &self.profiles[index] {
+            Some(profile) => profile.name.clone(),
+            None => format!("Player {}", index + 1),
+        }
+    }
+
+    /// Returns the short label of the player with the given index shown in the heads-up
+    /// display: the name of its profile, or e.g. "P1" for a guest.
+    pub fn player_label(&self, index: usize) -> String {
+        match &self.profiles[index] {
+            Some(profile) => profile.name.clone(),
+            None => format!("P{}", index + 1),
+        }
+    }
+
+    /// Sets whether the player with the given index is controlled by the AI.
+    pub fn set_ai(&mut self, index: usize, ai: bool) {
+        if self.players[index].is_ai() != ai {
+            self.players[index].toggle_ai();
+            self.emit(GameEvent::AiToggled { player: index, ai });
+        }
+    }
+
+    /// Sets the Bot that steers the player with the given index whenever it is controlled by
+    /// the AI, in place of the built-in AI, or removes it if `bot` is None.  A player with a
+    /// Bot starts every round controlled by the AI.
+    pub fn set_bot(&mut self, index: usize, bot: Option<Box<dyn Bot>>) {
+        self.set_ai(index, bot.is_some());
+        self.bots[index] = bot;
+    }
+
+    /// Returns true if any player has a Bot (see `set_bot`).
+    pub fn has_bots(&self) -> bool {
+        self.bots.iter().any(Option::is_some)
+    }
+
+    /// Returns a snapshot of the board as seen by the player with the given index, as given
+    /// to its Bot.
+    pub fn board_view(&self, index: usize) -> BoardView {
+        let players = self
+            .players
+            .iter()
+            .map(|player| PlayerView {
+                head: player.head(),
+                direction: player.moving_direction(),
+                crashed: player.has_crashed(),
+            })
+            .collect();
+        let free = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Block { x, y }))
+            .map(|block| self.collision(index, block).is_none())
+            .collect();
+        BoardView::new(self.width, self.height, index, players, self.food, free)
+    }
+
+    /// Returns every event that has happened since the events were last taken, oldest first.
+    /// Frontends take the events after each update and hand them to everything that reacts
+    /// to the game.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        self.events.drain(..).collect()
+    }
+
+    /// Adds an event to the ones waiting to be taken.
+    fn emit(&mut self, event: GameEvent) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Applies the given change to the player with the given index, and reports a Turned
+    /// event if it changed the direction the player is moving in.
+    fn steer(&mut self, index: usize, change: impl FnOnce(&mut Player)) {
+        let before = self.players[index].moving_direction();
+        change(&mut self.players[index]);
+        let direction = self.players[index].moving_direction();
+        if direction != before {
+            self.emit(GameEvent::Turned {
+                player: index,
+                direction,
+            });
+        }
+    }
+
+    /// Returns the Settings the game is currently being played with.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Changes the Settings the game is played with.  If any of the rules of the round have
+    /// changed the round is restarted, and if the number of players or the team mode has
+    /// changed the scores are reset as well.  Options that only change how the game is drawn
+    /// take effect immediately, as does the depth of the players' input queues.
+    pub fn apply_settings(&mut self, settings: Settings) {
+        let old = self.settings;
+        self.settings = settings;
+        for player in &mut self.players {
+            player.set_queue_depth(settings.input_queue);
+        }
+
+        if old.player_count != settings.player_count || old.team_mode != settings.team_mode {
+            self.scores = [0; MAX_PLAYERS];
+            self.restart();
+        } else if old.trail_mode != settings.trail_mode
+            || old.sudden_death != settings.sudden_death
+            || old.random_spawns != settings.random_spawns
+            || old.arena != settings.arena
+        {
+            self.restart();
+        }
+    }
+
+    /// Returns the seed the game's random numbers were first drawn from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Starts drawing the game's random numbers from the given seed, and restarts the round
+    /// so it is played out from the seed as well.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.restart();
+    }
+
+    /// Returns the Theme the game is drawn in.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Changes the Theme the game is drawn in.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Returns the time since the start of the round, in seconds.  The clock stops when the
+    /// round is over.
+    pub fn round_time(&self) -> f64 {
+        self.round_time
+    }
+
+    /// Returns the time left before the players start moving, in seconds, or None once the
+    /// countdown at the start of the round is over.
+    pub fn countdown(&self) -> Option<f64> {
+        if self.countdown > 0.0 {
+            Some(self.countdown)
+        } else {
+            None
+        }
+    }
+
+    /// Ends the countdown at the start of the round, so the players start moving on the next
+    /// update.  Used when nobody is watching, e.g. when exporting a round played by the AI.
+    pub fn skip_countdown(&mut self) {
+        self.countdown = 0.0;
+    }
+
+    /// Returns the number of whole ticks (`PLAYER_SPEED` steps) since the round started.
+    pub fn ticks(&self) -> u32 {
+        (self.round_time / PLAYER_SPEED) as u32
+    }
+
+    /// In sudden-death mode, returns the number of seconds left until the next ring of the
+    /// board closes.  Returns None if sudden death is off, the round is over, or no more rings
+    /// can be closed.
+    pub fn sudden_death_countdown(&self) -> Option<f64> {
+        let SuddenDeath {
+            start_after,
+            interval,
+        } = self.settings.sudden_death?;
+        if self.is_game_over || self.rings_closed >= self.max_rings() {
+            return None;
+        }
+        let next_close = start_after + self.rings_closed * interval.max(1);
+        Some((next_close as f64 * PLAYER_SPEED - self.round_time).max(0.0))
+    }
+
     /// Given an amount of time elapsed (this will be provided by the game window itself),
-    /// updates both of the players to account for this passed time (both of them should have
+    /// updates all of the players to account for this passed time (all of them should have
     /// their wait_time methods called).  If this causes time_waited to meet or exceed the
-    /// constant `PLAYER_SPEED` for either (or both) players, then the appropriate players should
-    /// be moved forward ONLY after verifying that they are not going to lose if they do so.
-    /// If they will lose, then the game is over and the proper winner should be indicated.
+    /// constant `PLAYER_SPEED` for any players, then those players should be moved forward
+    /// ONLY after verifying that they are not going to crash if they do so.  A player that
+    /// would crash stops moving, as do players moving onto the same Block at the same time,
+    /// and once only one team has players left the game is over and that team is the winner.
+    /// Nothing moves until the countdown at the start of the round is over.  Everything that
+    /// happens is reported as a GameEvent.
     pub fn update(&mut self, time_elapsed: f64) {
-        self.player_one.wait_time(time_elapsed);
-        self.player_two.wait_time(time_elapsed);
+        // the players only start moving once the countdown is over, with whatever time is
+        // left of this update
+        let time_elapsed = if self.countdown > 0.0 {
+            self.countdown -= time_elapsed;
+            if self.countdown > 0.0 {
+                return;
+            }
+            let leftover = -self.countdown;
+            self.countdown = 0.0;
+            leftover
+        } else {
+            time_elapsed
+        };
+
+        for player in &mut self.players {
+            player.wait_time(time_elapsed);
+        }
 
         if !self.is_game_over {
-            if self.player_one.time_waited() >= PLAYER_SPEED && !self.ai {
-                if self.player_one_collision(self.player_one.next_head_position()) {
-                    self.is_game_over = true;
-                    self.winner = Some(false);
-                } else {
-                    self.player_one.move_forward();
-                    self.player_one.advance_direction_queue();
-                    self.player_one.wait_time(-PLAYER_SPEED);
-                }
+            self.round_time += time_elapsed;
+            self.close_rings();
+        }
+
+        if self.is_game_over {
+            return;
+        }
+        // Every player that is due to move picks the Block it moves to before anyone moves, so
+        // players moving at the same time are treated alike whatever order they are in.
+        let mut moves = Vec::new();
+        let mut crashes = Vec::new();
+        for index in 0..self.players.len() {
+            let player = &self.players[index];
+            if player.has_crashed() || player.time_waited() < PLAYER_SPEED {
+                continue;
             }
-            // if AI enabled
-            else if self.player_one.time_waited() >= PLAYER_SPEED {
+
+            let chosen = player.is_ai() && self.update_bot_direction(index);
+            let mut next = self.players[index].next_head_position();
+            let mut collision = self.collision(index, next);
+            if collision.is_some() && self.players[index].is_ai() && !chosen {
                 // if about to crash, turn
-                if self.player_one_collision(self.player_one.next_head_position()) {
-                    self.update_ai_direction();
-                    if self.player_one_collision(self.player_one.next_head_position()) {
-                        self.is_game_over = true;
-                        self.winner = Some(false);
-                    }
-                } else {
-                    self.player_one.move_forward();
-                    self.player_one.advance_direction_queue();
-                    self.player_one.wait_time(-PLAYER_SPEED);
-                }
+                self.update_ai_direction(index);
+                next = self.players[index].next_head_position();
+                collision = self.collision(index, next);
+            }
+            match collision {
+                Some(kind) => crashes.push((index, next, kind)),
+                None => moves.push((index, next)),
             }
+        }
 
-            if self.player_two.time_waited() >= PLAYER_SPEED {
-                if self.player_two_collision() {
-                    self.is_game_over = true;
-                    self.winner = Some(true);
-                } else {
-                    self.player_two.move_forward();
-                    self.player_two.advance_direction_queue();
-                    self.player_two.wait_time(-PLAYER_SPEED);
-                }
+        // players moving onto the same Block crash into each other head-on
+        let head_on: Vec<_> = moves
+            .iter()
+            .filter_map(|&(index, next)| {
+                moves
+                    .iter()
+                    .find(|&&(other, block)| block == next && self.can_collide(index, other))
+                    .map(|&(other, _)| (index, next, CollisionKind::HeadOn(other)))
+            })
+            .collect();
+        moves.retain(|&(index, _)| !head_on.iter().any(|&(crashed, _, _)| crashed == index));
+        crashes.extend(head_on);
+        crashes.sort_by_key(|&(index, _, _)| index);
+
+        for (index, site, kind) in crashes {
+            self.crash_player(index, site, kind);
+        }
+        for (index, _) in moves {
+            let player = &mut self.players[index];
+            player.move_forward();
+            player.wait_time(-PLAYER_SPEED);
+            let to = player.head();
+            self.emit(GameEvent::Moved { player: index, to });
+            self.steer(index, Player::advance_direction_queue);
+            self.check_food(index);
+        }
+        self.check_round_over();
+    }
+
+    /// The number of rings that can be closed in sudden death while still leaving at least
+    /// one Block of playable area.  Boards too small to close any ring have none.
+    pub fn max_rings(&self) -> u32 {
+        (self.width.min(self.height).saturating_sub(1) / 2).saturating_sub(1)
+    }
+
+    /// In sudden-death mode, closes every ring of the board that is due to be closed by now.
+    /// Any player whose head is inside a newly closed ring crashes.
+    fn close_rings(&mut self) {
+        let SuddenDeath {
+            start_after,
+            interval,
+        } = match self.settings.sudden_death {
+            Some(sudden_death) => sudden_death,
+            None => return,
+        };
+        let ticks = self.ticks();
+        if ticks < start_after {
+            return;
+        }
+        let due = (1 + (ticks - start_after) / interval.max(1)).min(self.max_rings());
+        if due == self.rings_closed {
+            return;
+        }
+        self.rings_closed = due;
+
+        for index in 0..self.players.len() {
+            let player = &self.players[index];
+            if !player.has_crashed() && self.is_out_of_bounds(player.head()) {
+                self.crash_player(index, player.head(), CollisionKind::Wall);
             }
         }
+        self.check_round_over();
+        if self.food.is_some_and(|food| self.is_out_of_bounds(food)) {
+            self.place_food();
+        }
+    }
+
+    /// Marks the player with the given index as having crashed into the given Block, with the
+    /// given kind of collision.  Whether the game is over is left to check_round_over, so that
+    /// several players can crash at the same time and the round is a draw if nobody is left.
+    fn crash_player(&mut self, index: usize, site: Block, kind: CollisionKind) {
+        self.players[index].crash(site, kind);
+        self.emit(GameEvent::Crashed {
+            player: index,
+            cause: kind,
+            cell: site,
+        });
+    }
+
+    /// Ends the round if only one team (or no team) has players that haven't crashed, and
+    /// gives that team the win.
+    fn check_round_over(&mut self) {
+        if self.is_game_over {
+            return;
+        }
+        let mode = self.settings.team_mode;
+        let mut surviving_teams = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| !player.has_crashed())
+            .map(|(index, _)| team::team_of(index, mode));
+        let first = surviving_teams.next();
+        if surviving_teams.all(|team| Some(team) == first) {
+            self.is_game_over = true;
+            self.winner = first;
+            if let Some(team) = first {
+                self.scores[team] += 1;
+            }
+            self.emit(GameEvent::RoundOver { winner: first });
+        }
+    }
+
+    /// Checks whether the player with the given index has just moved onto the food item.  If
+    /// so, the player grows and a new food item is placed.
+    fn check_food(&mut self, index: usize) {
+        let player = &mut self.players[index];
+        if self.food == Some(player.head()) {
+            if let TrailMode::Snake { growth, .. } = self.settings.trail_mode {
+                player.grow(growth);
+            }
+            self.place_food();
+        }
+    }
+
+    /// Moves the food item to a random location on the board that is not out of bounds and not
+    /// covered by any player or obstacle.  If there is no such location, the food item is
+    /// removed.
+    fn place_food(&mut self) {
+        let free: Vec<Block> = (1..self.width - 1)
+            .flat_map(|x| (1..self.height - 1).map(move |y| Block { x, y }))
+            .filter(|&block| {
+                !self.is_out_of_bounds(block)
+                    && !self.obstacles.contains(&block)
+                    && !self
+                        .players
+                        .iter()
+                        .any(|player| player.trail_covers_location(block))
+            })
+            .collect();
+        self.food = if free.is_empty() {
+            None
+        } else {
+            Some(free[self.rng.gen_range(0..free.len())])
+        };
+    }
+
+    /// Creates the players for a new round according to the game's settings, then applies
+    /// the trail mode.  With an arena other than Open, a new arena is generated by
+    /// `arena::generate` and the players start at its spawns, since corners may be blocked.
+    /// Otherwise, with random spawns the players start at mirrored spots picked by
+    /// `spawn::symmetric_spawns`.  If the board is too small for either, the board is left
+    /// open and the players start in the corners.
+    fn spawn_players(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let count = self.settings.player_count.clamp(2, MAX_PLAYERS);
+
+        self.obstacles.clear();
+        let spawns = if self.settings.arena != ArenaStyle::Open {
+            let seed = self.rng.gen();
+            arena::generate(self.settings.arena, count, width, height, seed).map(|arena| {
+                self.obstacles = arena.obstacles;
+                arena.spawns
+            })
+        } else {
+            None
+        };
+        let spawns = match spawns {
+            None if self.settings.random_spawns => {
+                spawn::symmetric_spawns(count, width, height, &[], self.rng.gen())
+            }
+            spawns => spawns,
+        };
+        self.players = match spawns {
+            Some(spawns) => spawns.into_iter().map(Player::spawned).collect(),
+            None => (0..count)
+                .map(|index| Player::numbered(index, width, height))
+                .collect(),
+        };
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.set_queue_depth(self.settings.input_queue);
+            if self.bots[index].is_some() {
+                player.toggle_ai();
+            }
+        }
+
+        self.apply_trail_mode();
+    }
+
+    /// Applies the trail mode in the game's settings to all players and places or removes the
+    /// food item accordingly.
+    fn apply_trail_mode(&mut self) {
+        let max_length = self.settings.trail_mode.max_length();
+        for player in &mut self.players {
+            player.set_max_length(max_length);
+        }
+
+        if self.settings.trail_mode.has_food() {
+            self.place_food();
+        } else {
+            self.food = None;
+        }
     }
 
-    /// Updates player 1 to turn away from a wall in a direction that will keep it alive.
-    /// This forces the other player to make a risky move to beat the AI.
-    pub fn update_ai_direction(&mut self) {
-        if !self.player_one_collision(self.player_one.position_on_turn()) {
-            self.player_one.turn(false)
-        } else if !self.player_one_collision(self.player_one.position_on_cc()) {
-            self.player_one.turn(true);
+    /// Asks the Bot of the player with the given index, if it has one, which way to move next
+    /// and steers the player that way.  Returns true if the Bot chose a direction, in which
+    /// case the built-in AI leaves the player alone for this move.
+    fn update_bot_direction(&mut self, index: usize) -> bool {
+        let Some(mut bot) = self.bots[index].take() else {
+            return false;
+        };
+        let direction = bot.choose(&self.board_view(index));
+        self.bots[index] = Some(bot);
+        if let Some(direction) = direction {
+            self.steer(index, |player| player.update_direction(Some(direction)));
         }
+        direction.is_some()
     }
 
-    /// Updates the game based on a key pressed by the user.  The WASD keys will change the
-    /// direction of player 1 (do this by calling the update_direction method for player 1).
-    /// However, they will do nothing if player one is currently being controlled by the AI.
-    /// The directional arrow keys will update the direction of player 2 (do this by calling the
-    /// update_direction method for player 2).  The enter key will restart the game, but only
-    /// if the game is currently over.  The p key toggles the AI on and off for player 1.
+    /// Updates the player with the given index to turn away from a wall in a direction that
+    /// will keep it alive.  This forces the other players to make a risky move to beat the AI.
+    pub fn update_ai_direction(&mut self, index: usize) {
+        let player = &self.players[index];
+        if self.collision(index, player.position_on_turn()).is_none() {
+            self.steer(index, |player| player.turn(false));
+        } else if self.collision(index, player.position_on_cc()).is_none() {
+            self.steer(index, |player| player.turn(true));
+        }
+    }
+
+    /// Updates the game based on a key pressed by the user.  Each player has its own set of
+    /// direction keys (see `Controls`): WASD for player 1, the arrow keys for player 2, IJKL
+    /// for player 3 and the number pad for player 4, unless their profiles prefer other
+    /// keys (see `profile::assign_controls`).  These call the update_direction method
+    /// of that player, but do nothing if the player is being controlled by the AI.  The enter
+    /// key will restart the game, but only if the game is currently over.  The p key toggles
+    /// the AI on and off for player 1.  The m key switches between the classic permanent
+    /// trails and snake mode, the b key switches sudden-death mode on and off, the n key
+    /// changes the number of players and the t key switches between free-for-all, teams, and
+    /// teams with friendly fire.  All of these restart the round, and the n and t keys also
+    /// reset the scores.  The v key switches smooth movement on and off and the g key switches
+    /// glowing trails on and off, which only change how the players are drawn.
+    #[allow(clippy::collapsible_match)]
     pub fn key_pressed(&mut self, key: Key) {
         match key {
-            Key::W => {
-                if !self.ai {
-                    self.player_one.update_direction(Some(Direction::Up))
-                }
+            Key::P => self.set_ai(0, !self.players[0].is_ai()),
+            Key::M => {
+                let mut settings = self.settings;
+                settings.toggle_snake_mode();
+                self.apply_settings(settings);
             }
-            Key::A => {
-                if !self.ai {
-                    self.player_one.update_direction(Some(Direction::Left))
-                }
+            Key::B => {
+                let mut settings = self.settings;
+                settings.toggle_sudden_death();
+                self.apply_settings(settings);
             }
-            Key::S => {
-                if !self.ai {
-                    self.player_one.update_direction(Some(Direction::Down))
-                }
+            Key::N => {
+                let mut settings = self.settings;
+                settings.cycle_player_count(true);
+                self.apply_settings(settings);
             }
-            Key::D => {
-                if !self.ai {
-                    self.player_one.update_direction(Some(Direction::Right))
-                }
+            Key::T => {
+                let mut settings = self.settings;
+                settings.cycle_team_mode(true);
+                self.apply_settings(settings);
             }
-            Key::Up => self.player_two.update_direction(Some(Direction::Up)),
-            Key::Down => self.player_two.update_direction(Some(Direction::Down)),
-            Key::Left => self.player_two.update_direction(Some(Direction::Left)),
-            Key::Right => self.player_two.update_direction(Some(Direction::Right)),
-            Key::P => self.ai = !self.ai,
+            Key::V => self.settings.smooth_movement = !self.settings.smooth_movement,
+            Key::G => self.settings.glow = !self.settings.glow,
             Key::Return => {
                 if self.is_game_over {
                     self.restart();
                 }
             }
-            _ => {}
+            _ => {
+                if let Some((controls, direction)) = Controls::of_key(key) {
+                    let index = self.controls.iter().position(|&c| c == controls);
+                    let human = |index: &usize| {
+                        self.players
+                            .get(*index)
+                            .is_some_and(|player| !player.is_ai())
+                    };
+                    if let Some(index) = index.filter(human) {
+                        if self.countdown > 0.0 {
+                            // choosing the direction to start the round in
+                            self.steer(index, |player| player.face(direction));
+                        } else {
+                            self.steer(index, |player| player.update_direction(Some(direction)));
+                        }
+                    }
+                }
+            }
         }
     }
 
     /// Resets the state of the game to represent a brand new game by creating new
-    /// players and resetting is_game_over and time_waited.
+    /// players, resetting is_game_over and time_waited and starting the countdown again.
     pub fn restart(&mut self) {
-        self.player_one = Player::player_1();
-        self.player_two = Player::player_2(self.width, self.height);
-
         self.winner = None;
 
         self.is_game_over = false;
-        self.ai = false;
+
+        self.countdown = self.settings.countdown as f64;
+        self.round_time = 0.0;
+        self.rings_closed = 0;
+
+        self.spawn_players();
+        self.emit(GameEvent::RoundStarted);
     }
 
     /// Checks if the given Block (i.e., a location) is out of the bounds of the gameboard.
-    /// This will be used when determining if a snake has run out of bounds (i.e., died)
+    /// This will be used when determining if a snake has run out of bounds (i.e., died).
+    /// Rings closed in sudden death count as out of bounds.
     fn is_out_of_bounds(&self, block: Block) -> bool {
-        block.x == 0 || block.x >= (self.width - 1) || block.y == 0 || block.y >= (self.height - 1)
+        let border = self.border_width();
+        block.x < border
+            || block.x >= (self.width - border)
+            || block.y < border
+            || block.y >= (self.height - border)
     }
 
-    /// Checks if player one will be facing a collision in its next movement by
-    /// 1. if the player is out of bounds
-    /// 2. if the player crashes into itself
-    /// 3. if the player crashes into the other snake
-    fn player_one_collision(&mut self, position: Block) -> bool {
-        self.is_out_of_bounds(position)
-            || self.player_one.imminent_self_collision()
-            || self.player_two.trail_covers_location(position)
+    /// Checks if the player with the given index would collide with something by moving its
+    /// head to the given position, and returns what it would run into:
+    /// 1. the wall, if the given position is out of bounds
+    /// 2. an obstacle of the arena
+    /// 3. the player's own trail
+    /// 4. the head of another player, if it is on the given position, or else another
+    ///    player's trail.  When friendly fire is off in team mode, teammates don't count.
+    ///
+    /// Returns None if the move is safe.
+    fn collision(&self, index: usize, position: Block) -> Option<CollisionKind> {
+        if self.is_out_of_bounds(position) {
+            return Some(CollisionKind::Wall);
+        }
+        if self.obstacles.contains(&position) {
+            return Some(CollisionKind::Obstacle);
+        }
+        if self.players[index].trail_covers_location(position) {
+            return Some(CollisionKind::SelfTrail);
+        }
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| self.can_collide(index, other))
+            .find(|(_, player)| player.trail_covers_location(position))
+            .map(|(other, player)| {
+                if player.head() == position {
+                    CollisionKind::HeadOn(other)
+                } else {
+                    CollisionKind::OpponentTrail(other)
+                }
+            })
     }
 
-    /// Checks if player two will be facing a collision in its next movement
-    fn player_two_collision(&mut self) -> bool {
-        self.is_out_of_bounds(self.player_two.next_head_position())
-            || self.player_two.imminent_self_collision()
-            || self
-                .player_one
-                .trail_covers_location(self.player_two.next_head_position())
+    /// Returns whether the player with the given index can crash into the player with the
+    /// other index, which it can't if they are the same player, or teammates while friendly
+    /// fire is off.
+    fn can_collide(&self, index: usize, other: usize) -> bool {
+        let mode = self.settings.team_mode;
+        let friendly_fire = match mode {
+            TeamMode::FreeForAll => true,
+            TeamMode::Teams { friendly_fire } => friendly_fire,
+        };
+        other != index
+            && (friendly_fire || team::team_of(other, mode) != team::team_of(index, mode))
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use crate::graphics::Block;
+    use crate::player::Direction;
+    use crate::spawn::Spawn;
+    // the tests of how a round is played also run in the browser build, with
+    // `cargo test --target wasm32-unknown-unknown`
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
 
     #[test]
     fn test_new() {
         let game = Game::new(35, 25);
         assert_eq!(35, game.width);
         assert_eq!(25, game.height);
-        assert_eq!(Block { x: 5, y: 3 }, game.player_one.next_head_position());
-        assert_eq!(Block { x: 31, y: 19 }, game.player_two.next_head_position());
+        assert_eq!(2, game.players.len());
+        assert_eq!(Block { x: 5, y: 3 }, game.players[0].next_head_position());
+        assert_eq!(Block { x: 31, y: 19 }, game.players[1].next_head_position());
         assert_eq!(false, game.is_game_over);
     }
 
     #[test]
     fn test_update() {
         let mut game = Game::new(35, 25);
+        game.skip_countdown();
         game.update(0.08);
-        assert_eq!(0.08, game.player_one.time_waited());
+        assert_eq!(0.08, game.players[0].time_waited());
 
         game.update(0.08);
-        assert_eq!(0.16 - PLAYER_SPEED, game.player_one.time_waited());
+        assert_eq!(0.16 - PLAYER_SPEED, game.players[0].time_waited());
     }
 
     #[test]
     fn test_key_pressed() {
         let mut game = Game::new(35, 25);
+        game.skip_countdown();
         game.update(PLAYER_SPEED + 0.1);
         game.key_pressed(Key::S);
         game.key_pressed(Key::D);
         game.key_pressed(Key::Left);
 
-        assert_eq!(Block { x: 5, y: 4 }, game.player_one.next_head_position());
-        assert_eq!(Block { x: 30, y: 19 }, game.player_two.next_head_position())
+        assert_eq!(Block { x: 5, y: 4 }, game.players[0].next_head_position());
+        assert_eq!(Block { x: 30, y: 19 }, game.players[1].next_head_position())
+    }
+
+    /// A Bot that always heads down.
+    struct Downward;
+
+    impl Bot for Downward {
+        fn choose(&mut self, _board: &BoardView) -> Option<Direction> {
+            Some(Direction::Down)
+        }
+    }
+
+    #[test]
+    fn test_bot() {
+        let mut game = Game::new(35, 25);
+        game.set_bot(0, Some(Box::new(Downward)));
+        assert!(game.players[0].is_ai());
+        let board = game.board_view(0);
+        assert!(!board.is_free(4, 3));
+        assert!(board.is_free(10, 10));
+        assert!(!board.is_free(0, 3));
+
+        // the player turns after its first move, like a player pressing a key at the start
+        game.skip_countdown();
+        game.key_pressed(Key::W);
+        game.update(PLAYER_SPEED + 0.01);
+        game.update(PLAYER_SPEED);
+        assert_eq!(Block { x: 5, y: 4 }, game.players[0].head());
+
+        // the bot carries on steering in the next round, until it is removed
+        game.restart();
+        assert!(game.players[0].is_ai());
+        game.set_bot(0, None);
+        assert!(!game.players[0].is_ai());
+    }
+
+    #[test]
+    fn test_queued_turns() {
+        // down then left within one move makes a U-turn, one Block at a time
+        let mut game = Game::new(35, 25);
+        game.skip_countdown();
+        game.update(PLAYER_SPEED + 0.01);
+        game.key_pressed(Key::S);
+        game.key_pressed(Key::A);
+        game.update(PLAYER_SPEED);
+        assert_eq!(Block { x: 5, y: 4 }, game.players[0].head());
+        game.update(PLAYER_SPEED);
+        assert_eq!(Block { x: 4, y: 4 }, game.players[0].head());
+        let turns = game
+            .take_events()
+            .into_iter()
+            .filter(|event| matches!(event, GameEvent::Turned { player: 0, .. }))
+            .count();
+        assert_eq!(2, turns);
+
+        // without a queue the second key is dropped
+        let settings = Settings {
+            input_queue: 0,
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        game.skip_countdown();
+        game.update(PLAYER_SPEED + 0.01);
+        game.key_pressed(Key::S);
+        game.key_pressed(Key::A);
+        game.update(PLAYER_SPEED);
+        game.update(PLAYER_SPEED);
+        assert_eq!(Block { x: 5, y: 5 }, game.players[0].head());
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_events() {
+        let mut game = Game::new(35, 25);
+        game.skip_countdown();
+        game.update(PLAYER_SPEED + 0.01);
+        game.key_pressed(Key::S);
+        game.key_pressed(Key::P);
+        let events = game.take_events();
+        assert_eq!(
+            vec![
+                GameEvent::Moved {
+                    player: 0,
+                    to: Block { x: 5, y: 3 }
+                },
+                GameEvent::Moved {
+                    player: 1,
+                    to: Block { x: 31, y: 19 }
+                },
+                GameEvent::Turned {
+                    player: 0,
+                    direction: Direction::Down
+                },
+                GameEvent::AiToggled {
+                    player: 0,
+                    ai: true
+                },
+            ],
+            events
+        );
+        assert!(game.take_events().is_empty());
+
+        // player 2 drives into the wall to its right
+        game.key_pressed(Key::Right);
+        while !game.is_game_over() {
+            game.update(PLAYER_SPEED);
+        }
+        let events = game.take_events();
+        let crash = events
+            .iter()
+            .find(|event| matches!(event, GameEvent::Crashed { .. }));
+        assert_eq!(
+            Some(&GameEvent::Crashed {
+                player: 1,
+                cause: CollisionKind::Wall,
+                cell: Block { x: 34, y: 19 }
+            }),
+            crash
+        );
+        assert_eq!(
+            Some(&GameEvent::RoundOver { winner: Some(0) }),
+            events.last()
+        );
+
+        game.restart();
+        assert_eq!(vec![GameEvent::RoundStarted], game.take_events());
+    }
+
+    #[test]
+    fn test_countdown() {
+        let mut game = Game::new(35, 25);
+        assert_eq!(Some(3.0), game.countdown());
+        game.update(1.0);
+        assert_eq!(Some(2.0), game.countdown());
+        assert_eq!(Block { x: 4, y: 3 }, game.players[0].head());
+
+        // player 1 chooses to start upwards, but can't turn back into its own trail
+        game.key_pressed(Key::W);
+        game.key_pressed(Key::A);
+        game.update(2.0);
+        assert_eq!(None, game.countdown());
+        assert_eq!(Block { x: 4, y: 3 }, game.players[0].head());
+
+        // every player moves at the same moment
+        game.update(PLAYER_SPEED + 0.01);
+        assert_eq!(Block { x: 4, y: 2 }, game.players[0].head());
+        assert_eq!(Block { x: 31, y: 19 }, game.players[1].head());
+
+        game.restart();
+        assert_eq!(Some(3.0), game.countdown());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut game = Game::new(35, 25);
+        game.skip_countdown();
+        let profile = Profile {
+            name: String::from("Ada"),
+            color: Some([0.0, 1.0, 0.0, 1.0]),
+            controls: Some(Controls::Arrows),
+        };
+        game.set_profile(0, Some(profile));
+        assert_eq!("Ada", game.player_name(0));
+        assert_eq!("P2", game.player_label(1));
+        assert_eq!("Ada", game.team_of_player(0).name);
+        assert_eq!([0.0, 1.0, 0.0, 1.0], game.team_of_player(0).color);
+        assert_eq!(Controls::Arrows, game.controls(0));
+        assert_eq!(Controls::Wasd, game.controls(1));
+
+        // player 1 now steers with the arrow keys, and player 2 with WASD
+        game.update(PLAYER_SPEED + 0.1);
+        game.key_pressed(Key::Down);
+        game.key_pressed(Key::A);
+        assert_eq!(Block { x: 5, y: 4 }, game.players[0].next_head_position());
+        assert_eq!(Block { x: 30, y: 19 }, game.players[1].next_head_position());
+    }
+
+    #[test]
+    fn test_snake_mode_food() {
+        let settings = Settings {
+            trail_mode: TrailMode::Snake {
+                max_length: 3,
+                growth: 2,
+            },
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        game.skip_countdown();
+        let food = game.food.unwrap();
+        assert!(!game.players[0].trail_covers_location(food));
+        assert!(!game.is_out_of_bounds(food));
+
+        game.food = Some(Block { x: 5, y: 3 });
+        game.update(PLAYER_SPEED);
+        assert_eq!(Block { x: 5, y: 3 }, game.players[0].head());
+        assert_ne!(Some(Block { x: 5, y: 3 }), game.food);
+
+        game.food = None;
+        game.update(PLAYER_SPEED);
+        game.update(PLAYER_SPEED);
+        assert!(game.players[0].trail_covers_location(Block { x: 3, y: 3 }));
+        assert!(!game.players[0].trail_covers_location(Block { x: 2, y: 3 }));
+    }
+
+    #[test]
+    fn test_sudden_death() {
+        let settings = Settings {
+            sudden_death: Some(SuddenDeath {
+                start_after: 2,
+                interval: 3,
+            }),
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        game.skip_countdown();
+        assert_eq!(Some(2.0 * PLAYER_SPEED), game.sudden_death_countdown());
+
+        game.update(PLAYER_SPEED);
+        assert_eq!(0, game.rings_closed);
+        game.update(PLAYER_SPEED);
+        assert_eq!(1, game.rings_closed);
+        assert!(game.is_out_of_bounds(Block { x: 1, y: 10 }));
+        assert!(!game.is_out_of_bounds(Block { x: 2, y: 10 }));
+
+        game.update(3.0 * PLAYER_SPEED);
+        assert_eq!(2, game.rings_closed);
+
+        // both players' heads start on the third ring, so closing it ends the round in a draw
+        game.update(3.0 * PLAYER_SPEED);
+        assert!(game.is_game_over);
+        assert_eq!(None, game.winner());
+    }
+
+    #[test]
+    fn test_max_rings() {
+        let mut game = Game::new(35, 25);
+        assert_eq!(11, game.max_rings());
+        // a board smaller than 5 blocks across has no rings to close
+        for size in 0..5 {
+            game.width = size;
+            assert_eq!(0, game.max_rings());
+        }
+        game.width = 5;
+        assert_eq!(1, game.max_rings());
+    }
+
+    #[test]
+    fn test_random_spawns() {
+        let settings = Settings {
+            player_count: 4,
+            random_spawns: true,
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        let Block { x, y } = game.players[0].head();
+        assert_eq!(
+            Block {
+                x: 34 - x,
+                y: 24 - y
+            },
+            game.players[1].head()
+        );
+        assert_eq!(Block { x: 34 - x, y }, game.players[2].head());
+        assert_eq!(Block { x, y: 24 - y }, game.players[3].head());
+
+        game.apply_settings(Settings {
+            random_spawns: false,
+            ..settings
+        });
+        assert_eq!(Block { x: 4, y: 3 }, game.players[0].head());
+    }
+
+    #[test]
+    fn test_arena() {
+        let settings = Settings {
+            arena: ArenaStyle::Maze,
+            ..Settings::default()
+        };
+        let mut game = Game::with_seed(35, 25, settings, 7);
+        assert!(!game.obstacles.is_empty());
+        assert_eq!("Maze 35x25", game.map_name());
+        let obstacle = game.obstacles[0];
+        assert_eq!(Some(CollisionKind::Obstacle), game.collision(0, obstacle));
+        game.crash_player(0, obstacle, CollisionKind::Obstacle);
+        assert_eq!(
+            Some(String::from("Red Player crashed into an obstacle")),
+            game.crash_message(0)
+        );
+
+        // the board is too small for any obstacles
+        let small = Game::with_seed(12, 12, settings, 7);
+        assert!(small.obstacles.is_empty());
+        assert_eq!("Open 12x12", small.map_name());
+
+        game.apply_settings(Settings::default());
+        assert!(game.obstacles.is_empty());
+    }
+
+    #[test]
+    fn test_seed() {
+        let settings = Settings {
+            trail_mode: TrailMode::DEFAULT_SNAKE,
+            random_spawns: true,
+            ..Settings::default()
+        };
+        let game = Game::with_seed(35, 25, settings, 42);
+        let mut other = Game::with_settings(35, 25, settings);
+        other.set_seed(42);
+        assert_eq!(42, other.seed());
+        assert_eq!(game.food, other.food);
+        assert_eq!(game.players[0].head(), other.players[0].head());
+        assert_eq!(
+            game.players[0].moving_direction(),
+            other.players[0].moving_direction()
+        );
+    }
+
+    #[test]
+    fn test_teams() {
+        let settings = Settings {
+            player_count: 4,
+            team_mode: TeamMode::Teams {
+                friendly_fire: false,
+            },
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        assert_eq!(4, game.players.len());
+
+        // player 3 may pass through player 1's trail, but not through player 2's
+        assert_eq!(None, game.collision(2, Block { x: 3, y: 3 }));
+        assert_eq!(
+            Some(CollisionKind::OpponentTrail(1)),
+            game.collision(2, Block { x: 31, y: 21 })
+        );
+
+        game.crash_player(1, Block { x: 0, y: 0 }, CollisionKind::Wall);
+        game.check_round_over();
+        assert!(!game.is_game_over);
+        game.crash_player(2, Block { x: 0, y: 0 }, CollisionKind::Wall);
+        game.check_round_over();
+        assert!(!game.is_game_over);
+        assert_eq!(
+            Some(String::from("Player 3 crashed into the wall")),
+            game.crash_message(2)
+        );
+        game.crash_player(3, Block { x: 0, y: 0 }, CollisionKind::Wall);
+        game.check_round_over();
+        assert!(game.is_game_over);
+        assert_eq!("Red Team", game.winner().unwrap().name);
+        assert_eq!(1, game.score(0));
+        assert_eq!(0, game.score(1));
+    }
+
+    #[test]
+    fn test_friendly_fire() {
+        let settings = Settings {
+            player_count: 3,
+            team_mode: TeamMode::Teams {
+                friendly_fire: true,
+            },
+            ..Settings::default()
+        };
+        let game = Game::with_settings(35, 25, settings);
+        assert_eq!(
+            Some(CollisionKind::OpponentTrail(0)),
+            game.collision(2, Block { x: 3, y: 3 })
+        );
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_head_on() {
+        // players driving straight at each other reach the Block between them at the same
+        // time, and both crash however they are ordered
+        for gap in [3, 4] {
+            let mut game = Game::new(35, 25);
+            game.skip_countdown();
+            game.players[0] = Player::spawned(Spawn {
+                head: Block { x: 10, y: 12 },
+                direction: Direction::Right,
+            });
+            game.players[1] = Player::spawned(Spawn {
+                head: Block { x: 10 + gap, y: 12 },
+                direction: Direction::Left,
+            });
+            while !game.is_game_over() {
+                game.update(PLAYER_SPEED);
+            }
+            assert_eq!(Some(CollisionKind::HeadOn(1)), game.players[0].crash_kind());
+            assert_eq!(Some(CollisionKind::HeadOn(0)), game.players[1].crash_kind());
+            assert_eq!(Some(Block { x: 12, y: 12 }), game.players[0].crash_site());
+            assert_eq!(None, game.winner());
+            assert_eq!([0, 0], [game.score(0), game.score(1)]);
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_collision_kinds() {
+        let mut game = Game::new(35, 25);
+        assert_eq!(
+            Some(CollisionKind::Wall),
+            game.collision(0, Block { x: 0, y: 3 })
+        );
+        assert_eq!(
+            Some(CollisionKind::SelfTrail),
+            game.collision(0, Block { x: 3, y: 3 })
+        );
+        assert_eq!(
+            Some(CollisionKind::OpponentTrail(0)),
+            game.collision(1, Block { x: 3, y: 3 })
+        );
+        assert_eq!(
+            Some(CollisionKind::HeadOn(0)),
+            game.collision(1, Block { x: 4, y: 3 })
+        );
+        assert_eq!(None, game.collision(1, Block { x: 10, y: 10 }));
+
+        assert_eq!(None, game.crash_message(1));
+        game.crash_player(1, Block { x: 3, y: 3 }, CollisionKind::OpponentTrail(0));
+        assert_eq!(
+            Some(CollisionKind::OpponentTrail(0)),
+            game.players[1].crash_kind()
+        );
+        assert_eq!(
+            Some(String::from("Blue Player crashed into Red Player's trail")),
+            game.crash_message(1)
+        );
     }
 
     #[test]
@@ -1,20 +1,23 @@
 //! This file contains useful functions and constants for drawing
 //! the state of the game on the game window.
 
-use piston_window::types::Color;
-use piston_window::*;
+use piston_graphics::character::CharacterCache;
+use piston_graphics::types::{Color, FontSize};
+use piston_graphics::{polygon, rectangle, text, Context, Graphics, Transformed};
+use serde::{Deserialize, Serialize};
 
-/// Constants keeping track of the game's background color as well
-/// as the number of "points" (pixels on most machines) that each
-/// Block occupies in each dimension.
-pub const BACK_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 1.0];
+/// Constants keeping track of the color of the bars around the game when the
+/// window doesn't match its aspect ratio, as well as the number of "points" that
+/// each Block occupies in each dimension before the game is scaled to fit the
+/// window.  Every other color comes from the game's Theme.
+pub const LETTERBOX_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
 pub const POINTS_PER_BLOCK: f64 = 25.0;
 
 /// A Block simply contains an x and y coordinate representing its position
 /// on the game board.  The top left corner is (0, 0), x-values increase to
 /// the right all the way to the width - 1, and y-values increase going down
 /// all the way to the height - 1.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Block {
     pub x: u32,
     pub y: u32,
@@ -23,7 +26,7 @@ pub struct Block {
 impl Block {
     /// Draws the current Block in the given Color on the give Context and
     /// 2D Graphics object.
-    pub fn draw(&self, color: Color, con: &Context, g: &mut G2d) {
+    pub fn draw<G: Graphics>(&self, color: Color, con: &Context, g: &mut G) {
         draw_rectangle(color, self.x, self.y, 1, 1, con, g);
     }
 }
@@ -31,27 +34,192 @@ impl Block {
 /// Utility drawing function.  Draws a rectangle with the given Color and location.  The
 /// location should be understood as follows: the start x and y coordinates are Block
 /// coordinates, not pixels.  The width and height are also given in number of blocks.
-pub fn draw_rectangle(
+pub fn draw_rectangle<G: Graphics>(
     color: Color,
     start_x: u32,
     start_y: u32,
     width: u32,
     height: u32,
     con: &Context,
-    g: &mut G2d,
+    g: &mut G,
 ) {
-    let gui_start_x = (start_x as f64) * 25.0;
-    let gui_start_y = (start_y as f64) * 25.0;
+    let gui_start_x = (start_x as f64) * POINTS_PER_BLOCK;
+    let gui_start_y = (start_y as f64) * POINTS_PER_BLOCK;
 
     rectangle(
         color,
         [
             gui_start_x,
             gui_start_y,
-            25.0 * (width as f64),
-            25.0 * (height as f64),
+            POINTS_PER_BLOCK * (width as f64),
+            POINTS_PER_BLOCK * (height as f64),
         ],
         con.transform,
         g,
     );
 }
+
+/// Returns the given Color with its opacity multiplied by `opacity`.
+pub fn faded(color: Color, opacity: f32) -> Color {
+    [color[0], color[1], color[2], color[3] * opacity]
+}
+
+/// Utility drawing function.  Draws a path of horizontal and vertical segments through the
+/// given points, which are given in Blocks (so the center of Block (x, y) is at
+/// (x + 0.5, y + 0.5)).  The path is `width` Blocks thick, and both of its ends are squared
+/// off `width / 2` past the first and last points.  Segment `i` joins points `i` and `i + 1`
+/// and is drawn in the Color returned by `color(i)`.  Segments are joined without
+/// overlapping, so translucent colors are drawn evenly, and each segment takes a single draw
+/// call however long it is.  A path with a single point is drawn as a square.
+pub fn draw_path<F, G>(points: &[[f64; 2]], width: f64, color: F, con: &Context, g: &mut G)
+where
+    F: Fn(usize) -> Color,
+    G: Graphics,
+{
+    let half = width / 2.0;
+    if let [[x, y]] = points {
+        rectangle(
+            color(0),
+            [
+                (x - half) * POINTS_PER_BLOCK,
+                (y - half) * POINTS_PER_BLOCK,
+                width * POINTS_PER_BLOCK,
+                width * POINTS_PER_BLOCK,
+            ],
+            con.transform,
+            g,
+        );
+        return;
+    }
+
+    let last = points.len().saturating_sub(2);
+    for (segment, pair) in points.windows(2).enumerate() {
+        let ([ax, ay], [bx, by]) = (pair[0], pair[1]);
+        // f64::signum is 1 for zero, but a segment has no extent across its direction
+        let sign = |delta: f64| if delta == 0.0 { 0.0 } else { delta.signum() };
+        let (dx, dy) = (sign(bx - ax), sign(by - ay));
+        // Each segment covers the square around its first point, and stops at the edge of
+        // the square around its second point, which belongs to the next segment.
+        let start = [ax - dx * half, ay - dy * half];
+        let end = if segment == last {
+            [bx + dx * half, by + dy * half]
+        } else {
+            [bx - dx * half, by - dy * half]
+        };
+
+        let (mut left, mut right) = (start[0].min(end[0]), start[0].max(end[0]));
+        let (mut top, mut bottom) = (start[1].min(end[1]), start[1].max(end[1]));
+        if dx == 0.0 {
+            left -= half;
+            right += half;
+        }
+        if dy == 0.0 {
+            top -= half;
+            bottom += half;
+        }
+        rectangle(
+            color(segment),
+            [
+                left * POINTS_PER_BLOCK,
+                top * POINTS_PER_BLOCK,
+                (right - left) * POINTS_PER_BLOCK,
+                (bottom - top) * POINTS_PER_BLOCK,
+            ],
+            con.transform,
+            g,
+        );
+    }
+}
+
+/// Utility drawing function.  Draws an arrow-shaped triangle in the given Color, centered on
+/// `center` and pointing along `direction`.  Both are given in Blocks, and `direction` should
+/// have a length of 1.  The arrow fits within a single Block.
+pub fn draw_arrow<G: Graphics>(
+    color: Color,
+    center: [f64; 2],
+    direction: [f64; 2],
+    con: &Context,
+    g: &mut G,
+) {
+    let [dx, dy] = direction;
+    // the corners of an arrow pointing to the right, which are rotated to face `direction`
+    let points: Vec<[f64; 2]> = [[0.35, 0.0], [-0.25, -0.3], [-0.25, 0.3]]
+        .iter()
+        .map(|&[a, b]| {
+            [
+                (center[0] + a * dx - b * dy) * POINTS_PER_BLOCK,
+                (center[1] + a * dy + b * dx) * POINTS_PER_BLOCK,
+            ]
+        })
+        .collect();
+    polygon(color, &points, con.transform, g);
+}
+
+/// Returns a copy of the given Context whose transform maps an area of the given size (in
+/// points) onto the whole window.  The area is scaled by the same amount in both directions,
+/// so it keeps its aspect ratio, and is made as large as possible.  It is then centered, so
+/// any space left over forms bars on either side of it.
+pub fn fit_to_view(con: &Context, size: [f64; 2]) -> Context {
+    let [view_width, view_height] = con.get_view_size();
+    let scale = (view_width / size[0]).min(view_height / size[1]);
+    con.trans(
+        (view_width - size[0] * scale) / 2.0,
+        (view_height - size[1] * scale) / 2.0,
+    )
+    .scale(scale, scale)
+}
+
+/// The horizontal alignment of a line of text within the rectangle it is drawn in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Utility drawing function.  Draws a line of text in the given Color and font size inside
+/// the rectangle `[x, y, width, height]`, which is given in points rather than blocks.  The
+/// text is aligned horizontally within the rectangle according to `align` and is always
+/// centered vertically.  The width of the text is measured with the glyph cache, so the
+/// layout is correct for any message and font size.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text<G, C>(
+    msg: &str,
+    color: Color,
+    size: FontSize,
+    rect: [f64; 4],
+    align: Align,
+    con: &Context,
+    g: &mut G,
+    glyphs: &mut C,
+) where
+    G: Graphics,
+    C: CharacterCache<Texture = G::Texture>,
+    C::Error: std::fmt::Debug,
+{
+    let [x, y, width, height] = rect;
+    let text_width = glyphs.width(size, msg).unwrap_or(0.0);
+    // The distance from the baseline to the top of a capital letter, used to center the
+    // text vertically.
+    let ascent = glyphs
+        .character(size, 'A')
+        .map(|character| character.top())
+        .unwrap_or(size as f64);
+
+    let start_x = match align {
+        Align::Left => x,
+        Align::Center => x + (width - text_width) / 2.0,
+        Align::Right => x + width - text_width,
+    };
+    let baseline_y = y + (height + ascent) / 2.0;
+
+    text::Text::new_color(color, size)
+        .draw(
+            msg,
+            glyphs,
+            &con.draw_state,
+            con.transform.trans(start_x.round(), baseline_y.round()),
+            g,
+        )
+        .unwrap();
+}
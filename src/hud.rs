@@ -0,0 +1,165 @@
+//! This file contains the heads-up display drawn in a strip below the game board, showing
+//! live information about the round in progress, and the drawing of a complete frame of the
+//! game, which is shared by the window and by captured images.  What the text says comes
+//! from `status.rs`; everything here is drawn through the `Graphics` and `CharacterCache`
+//! traits, so it works with any backend.
+
+use crate::effects::Effects;
+use crate::game::Game;
+use crate::graphics;
+use crate::graphics::{draw_text, Align};
+use crate::status::{self, Text, COLUMNS, COUNTDOWN_SIZE, FONT_SIZE, HUD_HEIGHT, RESULT_SIZE};
+use piston_graphics::character::CharacterCache;
+use piston_graphics::{rectangle, Context, Graphics};
+
+/// Draws the heads-up display below the game board.  The first row shows the round timer,
+/// the speed of the players and the game's random seed on the left and, in sudden death, the
+/// time until the next ring closes on the right.  The second row shows the trail length of
+/// each player and whether it is controlled by the AI, and the third row shows how many
+/// rounds each team has won.  The text comes from `status::hud`, and the display is drawn
+/// in the colors of the game's theme.
+pub fn draw<G, C>(game: &Game, con: &Context, g: &mut G, glyphs: &mut C)
+where
+    G: Graphics,
+    C: CharacterCache<Texture = G::Texture>,
+    C::Error: std::fmt::Debug,
+{
+    let theme = game.theme();
+    let top = game.get_height();
+    graphics::draw_rectangle(theme.hud, 0, top, game.get_width(), HUD_HEIGHT, con, g);
+
+    let width = game.get_width() as f64 * graphics::POINTS_PER_BLOCK;
+    let column_width = (width - 16.0) / COLUMNS as f64;
+    let row = |row: u32| {
+        [
+            8.0,
+            (top + row) as f64 * graphics::POINTS_PER_BLOCK,
+            width - 16.0,
+            graphics::POINTS_PER_BLOCK,
+        ]
+    };
+    let cell = |row_index: u32, column: usize| {
+        let [x, y, _, height] = row(row_index);
+        [x + column_width * column as f64, y, column_width, height]
+    };
+    let mut draw_line = |line: &Text, rect: [f64; 4], align: Align| {
+        draw_text(
+            &line.text, line.color, FONT_SIZE, rect, align, con, g, glyphs,
+        );
+    };
+
+    let hud = status::hud(game);
+    draw_line(&hud.status, row(0), Align::Left);
+    if let Some(sudden_death) = &hud.sudden_death {
+        draw_line(sudden_death, row(0), Align::Right);
+    }
+    for (index, player) in hud.players.iter().enumerate() {
+        draw_line(player, cell(1, index), Align::Left);
+    }
+    for (index, score) in hud.scores.iter().enumerate() {
+        draw_line(score, cell(2, index), Align::Left);
+    }
+}
+
+/// Returns the size of a complete frame (the board and the heads-up display) in points.
+pub fn frame_size(game: &Game) -> [f64; 2] {
+    [
+        game.get_width() as f64 * graphics::POINTS_PER_BLOCK,
+        (game.get_height() + HUD_HEIGHT) as f64 * graphics::POINTS_PER_BLOCK,
+    ]
+}
+
+/// Draws a complete frame of the game: the background, the board, the visual effects, the
+/// heads-up display, the countdown before the round starts and, once the round is over, the
+/// game-over screen.  The frame is drawn at the size given by frame_size, starting at the
+/// origin of the given Context.
+pub fn draw_frame<G, C>(game: &Game, effects: &Effects, con: &Context, g: &mut G, glyphs: &mut C)
+where
+    G: Graphics,
+    C: CharacterCache<Texture = G::Texture>,
+    C::Error: std::fmt::Debug,
+{
+    let [width, height] = frame_size(game);
+    rectangle(
+        game.theme().background,
+        [0.0, 0.0, width, height],
+        con.transform,
+        g,
+    );
+    game.draw(con, g);
+    effects.draw(con, g);
+    draw(game, con, g, glyphs);
+    if let Some(countdown) = status::countdown(game) {
+        let board = [
+            0.0,
+            0.0,
+            width,
+            height - HUD_HEIGHT as f64 * graphics::POINTS_PER_BLOCK,
+        ];
+        draw_text(
+            &countdown.text,
+            countdown.color,
+            COUNTDOWN_SIZE,
+            board,
+            Align::Center,
+            con,
+            g,
+            glyphs,
+        );
+    }
+    if let Some(game_over) = status::game_over(game) {
+        draw_game_over(game, &game_over, con, g, glyphs);
+    }
+}
+
+/// Draws the given game-over screen over the board of the given Game: the board is tinted,
+/// and the result is written in the middle with what each crashed player ran into below it.
+fn draw_game_over<G, C>(
+    game: &Game,
+    game_over: &status::GameOver,
+    con: &Context,
+    g: &mut G,
+    glyphs: &mut C,
+) where
+    G: Graphics,
+    C: CharacterCache<Texture = G::Texture>,
+    C::Error: std::fmt::Debug,
+{
+    let tint = graphics::faded(game_over.tint, game.theme().overlay_opacity);
+    graphics::draw_rectangle(tint, 0, 0, game.get_width(), game.get_height(), con, g);
+
+    //drawing the text on the game over screen, centered on the board
+    let board = [
+        0.0,
+        0.0,
+        game.get_width() as f64 * graphics::POINTS_PER_BLOCK,
+        game.get_height() as f64 * graphics::POINTS_PER_BLOCK,
+    ];
+    let result = &game_over.result;
+    draw_text(
+        &result.text,
+        result.color,
+        RESULT_SIZE,
+        board,
+        Align::Center,
+        con,
+        g,
+        glyphs,
+    );
+
+    // how each player crashed, listed below the result
+    let line_height = graphics::POINTS_PER_BLOCK;
+    for (line, crash) in game_over.crashes.iter().enumerate() {
+        let y = board[3] / 2.0 + line_height * (1.5 + line as f64);
+        draw_text(
+            &crash.text,
+            crash.color,
+            FONT_SIZE,
+            [0.0, y, board[2], line_height],
+            Align::Center,
+            con,
+            g,
+            glyphs,
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Bloxide is a trail-based arcade game for up to four players.  The library contains the
+//! game itself, which doesn't depend on any particular window or screen, along with its
+//! frontends: the desktop window, the terminal and the browser.  Drawing is done through the
+//! `Graphics` trait of piston2d-graphics, so each frontend only has to provide a backend.
+
+pub mod arena;
+pub mod audio;
+pub mod bot;
+pub mod effects;
+pub mod event;
+pub mod game;
+pub mod graphics;
+pub mod player;
+pub mod profile;
+pub mod settings;
+pub mod spawn;
+pub mod status;
+pub mod team;
+pub mod theme;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capture;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hud;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod menu;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replay;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod save;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod script;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stats;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+
+/// The contents of the font all text is drawn in, embedded in the program so it doesn't
+/// have to be found on disk (or fetched, in the browser) at runtime.
+pub const FONT: &[u8] = include_bytes!("../assets/AtariClassic-gry3.ttf");
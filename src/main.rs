@@ -1,124 +1,373 @@
-extern crate piston_window;
+//! The desktop frontend, which plays the game in a window, or in the terminal with `--tui`.
+//! In the browser the game is run from the library instead (see `web.rs`).
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
 
-mod game;
-mod graphics;
-mod player;
+extern crate piston_window;
 
-use crate::game::Game;
-use gfx_device_gl::{CommandBuffer, Device, Factory, Resources};
-use piston_window::glyph_cache::rusttype::GlyphCache;
+use bloxide::audio::Audio;
+use bloxide::capture::{CaptureFormat, Recorder};
+use bloxide::config::Config;
+use bloxide::effects::Effects;
+use bloxide::game::{Game, BOARD_HEIGHT, BOARD_WIDTH};
+use bloxide::menu::SettingsMenu;
+use bloxide::profile::Profile;
+use bloxide::replay::{Replay, ReplayRecorder};
+use bloxide::script::ScriptBot;
+use bloxide::settings::MAX_PLAYERS;
+use bloxide::stats::History;
+use bloxide::{audio, capture, graphics, hud, save, status, tui};
 use piston_window::*;
+use std::path::PathBuf;
 
 fn main() {
-    // The dimensions of the game board, in "block" units.
-    let (width, height) = (35_u32, 25_u32);
+    let (width, height) = (BOARD_WIDTH, BOARD_HEIGHT);
+
+    // the theme and settings are remembered between runs in the config file
+    let mut config = Config::load();
+    let themes = config.themes();
+    let profiles = config.profiles();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // `--load <path>` resumes a saved game, which keeps its own rules and profiles, and goes on
+    // drawing its own random numbers, so it can't be given a seed as well
+    if args.iter().any(|arg| arg == "--load") && args.iter().any(|arg| arg == "--seed") {
+        eprintln!("--seed can't be used with --load, since a saved game has its own seed");
+        std::process::exit(1);
+    }
+    let mut game = match args.iter().position(|arg| arg == "--load") {
+        Some(index) => {
+            let path = match args.get(index + 1) {
+                Some(path) => PathBuf::from(path),
+                None => {
+                    eprintln!("--load needs the path of a saved game");
+                    std::process::exit(1);
+                }
+            };
+            save::load(&path).unwrap_or_else(|err| {
+                eprintln!("Couldn't load {}: {}", path.display(), err);
+                std::process::exit(1);
+            })
+        }
+        None => {
+            let mut game = Game::new(width, height);
+            game.apply_settings(config.settings);
+            seat_profiles(&mut game, &profiles, config.seated_profiles(&profiles));
+            game
+        }
+    };
+
+    // `--seed <number>` draws the game's random numbers from the given seed, so a match
+    // shown with that seed on the heads-up display can be played again exactly
+    if let Some(index) = args.iter().position(|arg| arg == "--seed") {
+        match args.get(index + 1).and_then(|seed| seed.parse().ok()) {
+            Some(seed) => game.set_seed(seed),
+            None => {
+                eprintln!("--seed needs a whole number to draw random numbers from");
+                std::process::exit(1);
+            }
+        }
+    }
+    game.set_theme(themes[config.selected_theme(&themes)].clone());
+
+    // `--bot <path>` lets a script steer player 1 in place of the built-in AI, reloading it
+    // whenever the file changes
+    if let Some(index) = args.iter().position(|arg| arg == "--bot") {
+        let path = match args.get(index + 1) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                eprintln!("--bot needs the path of a bot script");
+                std::process::exit(1);
+            }
+        };
+        match ScriptBot::load(&path) {
+            Ok(bot) => game.set_bot(0, Some(Box::new(bot))),
+            Err(err) => {
+                eprintln!("Couldn't load {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // `--export <path>` records a round without opening a window, as a GIF if the path ends
+    // in ".gif" and as a directory of PNG images otherwise.  With `--replay [path]` the round
+    // is played back from a replay (by default the last round played), and otherwise it is
+    // played by the AI.
+    if let Some(index) = args.iter().position(|arg| arg == "--export") {
+        let path = match args.get(index + 1) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                eprintln!("--export needs the path to save the round to");
+                std::process::exit(1);
+            }
+        };
+        let format = match path.extension() {
+            Some(extension) if extension == "gif" => CaptureFormat::Gif,
+            _ => CaptureFormat::Png,
+        };
+        let exported = match args.iter().position(|arg| arg == "--replay") {
+            Some(index) => {
+                let replay_path = match args.get(index + 1).filter(|arg| !arg.starts_with("--")) {
+                    Some(replay_path) => Some(PathBuf::from(replay_path)),
+                    None => Replay::default_path(),
+                };
+                replay_path
+                    .ok_or_else(|| String::from("no data directory on this platform"))
+                    .and_then(|replay_path| {
+                        Replay::load(&replay_path).map_err(|err| {
+                            format!("couldn't load {}: {}", replay_path.display(), err)
+                        })
+                    })
+                    .and_then(|replay| {
+                        capture::export_replay(&replay, game.theme().clone(), format, path)
+                    })
+            }
+            None => capture::export_ai_round(game, format, path),
+        };
+        match exported {
+            Ok(path) => println!("Saved the round to {}", path.display()),
+            Err(err) => {
+                eprintln!("Couldn't export the round: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // every round played is recorded in the match history, and the last one is kept as a
+    // replay that can be exported with `--export`
+    let mut history = History::load();
+    let mut replays = ReplayRecorder::new(Replay::default_path());
+
+    // `--tui` plays the game in the terminal instead of opening a window
+    if args.iter().any(|arg| arg == "--tui") {
+        if let Err(err) = tui::run(game, history, replays) {
+            eprintln!("Terminal error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     // Creating the frame within which the game is displayed
     let mut window: PistonWindow = WindowSettings::new(
         "Bloxide",
         [
             ((width as f64) * graphics::POINTS_PER_BLOCK) as u32,
-            ((height as f64) * graphics::POINTS_PER_BLOCK) as u32,
+            (((height + status::HUD_HEIGHT) as f64) * graphics::POINTS_PER_BLOCK) as u32,
         ],
     )
     .exit_on_esc(true)
-    .resizable(false)
+    .resizable(true)
     .build()
     .unwrap();
+    let mut fullscreen = false;
+
+    // The size of everything that is drawn (the board and the heads-up display) in points,
+    // before it is scaled to fit the window.
+    let logical_size = [
+        (width as f64) * graphics::POINTS_PER_BLOCK,
+        ((height + status::HUD_HEIGHT) as f64) * graphics::POINTS_PER_BLOCK,
+    ];
 
-    let mut game = Game::new(width, height);
+    let mut effects = Effects::new();
+    let mut audio = Audio::new(audio::default_backend(), config.audio);
+    let mut menu: Option<SettingsMenu> = None;
+    let mut stats_open = false;
 
-    // setup for font drawing
-    let assets = find_folder::Search::ParentsThenKids(3, 2)
-        .for_folder("assets")
-        .unwrap();
-    let font = &assets.join("AtariClassic-gry3.ttf");
+    // setup for font drawing, from the font embedded in the program
     let texture_context = piston_window::TextureContext {
         factory: window.factory.clone(),
         encoder: window.factory.create_command_buffer().into(),
     };
     let texture_settings = piston_window::TextureSettings::new();
-    let mut glyphs = Glyphs::new(font, texture_context, texture_settings).unwrap();
+    let mut glyphs = Glyphs::from_bytes(bloxide::FONT, texture_context, texture_settings).unwrap();
+
+    // screenshots and recordings are drawn in software, with their own glyph cache
+    let mut raster_glyphs = capture::raster_glyphs();
+    let mut recorder: Option<Recorder> = None;
 
     // main animation loop
     while let Some(event) = window.next() {
         if let Some(Button::Keyboard(key)) = event.press_args() {
             // handle key events
-            game.key_pressed(key);
+            if key == Key::F11 {
+                fullscreen = !fullscreen;
+                set_fullscreen(&window, fullscreen);
+            } else if key == Key::F12 {
+                match capture::screenshot(&game, &effects, &mut raster_glyphs) {
+                    Ok(path) => println!("Saved a screenshot to {}", path.display()),
+                    Err(err) => eprintln!("Couldn't save a screenshot: {}", err),
+                }
+            } else if key == Key::F5 {
+                // F5 saves the game, and F9 resumes the last saved game
+                match save::default_path().ok_or("no data directory on this platform") {
+                    Ok(path) => match save::save(&game, &path) {
+                        Ok(()) => println!("Saved the game to {}", path.display()),
+                        Err(err) => eprintln!("Couldn't save the game: {}", err),
+                    },
+                    Err(err) => eprintln!("Couldn't save the game: {}", err),
+                }
+            } else if key == Key::F9 {
+                let loaded = save::default_path()
+                    .ok_or_else(|| String::from("no data directory on this platform"))
+                    .and_then(|path| save::load(&path));
+                match loaded {
+                    Ok(mut loaded) => {
+                        loaded.set_theme(game.theme().clone());
+                        game = loaded;
+                        replays.stop();
+                        effects = Effects::new();
+                    }
+                    Err(err) => eprintln!("Couldn't load the saved game: {}", err),
+                }
+            } else if key == Key::F8 {
+                audio.toggle_mute();
+                config.audio = audio.settings();
+                if let Err(err) = config.save() {
+                    eprintln!("Couldn't save the config file: {}", err);
+                }
+            } else if key == Key::F10 {
+                // F10 starts recording, and stops early if pressed again
+                match recorder.take() {
+                    Some(stopped) => finish_recording(stopped),
+                    None => match Recorder::start_new(config.capture_format) {
+                        Ok(started) => recorder = Some(started),
+                        Err(err) => eprintln!("Couldn't start recording: {}", err),
+                    },
+                }
+            } else if stats_open {
+                // the H key closes the stats screen again, and other keys are ignored
+                stats_open = key != Key::H;
+            } else if key == Key::H && menu.is_none() {
+                stats_open = true;
+            } else if key == Key::Tab {
+                // Tab opens the settings menu, and closing it applies and saves the choices
+                match menu.take() {
+                    Some(closed) => {
+                        // the profiles may have changed the controls, so the round in
+                        // progress can't be replayed
+                        replays.stop();
+                        game.set_theme(closed.theme().clone());
+                        game.apply_settings(closed.settings());
+                        seat_profiles(&mut game, &profiles, closed.seats());
+                        audio.set_settings(closed.audio());
+                        config.audio = closed.audio();
+                        config.theme = closed.theme().name.clone();
+                        config.settings = closed.settings();
+                        config.seats = closed
+                            .seats()
+                            .iter()
+                            .map(|seat| seat.map_or(String::new(), |p| profiles[p].name.clone()))
+                            .collect();
+                        if let Err(err) = config.save() {
+                            eprintln!("Couldn't save the config file: {}", err);
+                        }
+                    }
+                    None => {
+                        let theme = themes
+                            .iter()
+                            .position(|theme| theme == game.theme())
+                            .unwrap_or(0);
+                        let seats: [Option<usize>; MAX_PLAYERS] = std::array::from_fn(|index| {
+                            let seated = game.profile(index);
+                            profiles.iter().position(|profile| Some(profile) == seated)
+                        });
+                        menu = Some(SettingsMenu::new(
+                            themes.clone(),
+                            theme,
+                            game.settings(),
+                            audio.settings(),
+                            profiles.clone(),
+                            seats,
+                        ));
+                    }
+                }
+            } else if let Some(menu) = &mut menu {
+                menu.key_pressed(key);
+            } else {
+                replays.key_pressed(key);
+                game.key_pressed(key);
+            }
         }
 
         window.draw_2d(&event, |c, g, dev| {
-            clear(graphics::BACK_COLOR, g);
-            let (winner, ai) = game.draw(&c, g);
-
-            // if the game is over, call a function that draws the appropriate
-            // game-over message.
-            if let Some(player) = winner {
-                game_over_screen(&game, player, ai, &c, g, dev, &mut glyphs)
+            clear(graphics::LETTERBOX_COLOR, g);
+            let c = graphics::fit_to_view(&c, logical_size);
+            hud::draw_frame(&game, &effects, &c, g, &mut glyphs);
+            if let Some(menu) = &menu {
+                menu.draw(
+                    [0.0, 0.0, logical_size[0], logical_size[1]],
+                    &c,
+                    g,
+                    &mut glyphs,
+                );
+            }
+            if stats_open {
+                history.draw(
+                    game.theme(),
+                    [0.0, 0.0, logical_size[0], logical_size[1]],
+                    &c,
+                    g,
+                    &mut glyphs,
+                );
             }
+            glyphs.factory.encoder.flush(dev);
         });
 
+        // the game is paused while the settings menu or the stats screen is open
+        audio.set_paused(menu.is_some() || stats_open);
+        if menu.is_some() || stats_open {
+            continue;
+        }
         event.update(|arg| {
-            // update game backend
+            // update game backend, then everything that reacts to what happened in it
+            if let Err(err) = replays.before_update(&game, arg.dt) {
+                eprintln!("Couldn't record a replay of the round: {}", err);
+            }
             game.update(arg.dt);
+            let events = game.take_events();
+            effects.update(&game, &events, arg.dt);
+            audio.update(&game, &events);
+            if let Err(err) = history.update(&game, &events) {
+                eprintln!("Couldn't save the round to the match history: {}", err);
+            }
+            if let Err(err) = replays.after_update(&events) {
+                eprintln!("Couldn't save the replay of the round: {}", err);
+            }
+
+            // a recording ends by itself shortly after the round does
+            let done = recorder.as_mut().is_some_and(|recorder| {
+                recorder.update(&game, &effects, &mut raster_glyphs, arg.dt)
+            });
+            if done {
+                finish_recording(recorder.take().unwrap());
+            }
         });
     }
 }
 
-/// Draws the game-over screen displaying which player won.  True represents the red player
-/// winning, false represents the blue player winning.
-fn game_over_screen(
-    game: &Game,
-    winner: bool,
-    ai: bool,
-    con: &Context,
-    g: &mut G2d,
-    dev: &mut Device,
-    glyphs: &mut GlyphCache<TextureContext<Factory, Resources, CommandBuffer>, Texture<Resources>>,
-) {
-    let main_color = if winner && ai {
-        [0., 1.0, 0., 0.15]
-    } else if winner {
-        [1.0, 0., 0., 0.15]
+/// Switches the window between fullscreen and windowed mode.
+fn set_fullscreen(window: &PistonWindow, fullscreen: bool) {
+    let monitor = if fullscreen {
+        Some(glutin::window::Fullscreen::Borderless(None))
     } else {
-        [0., 0., 1.0, 0.15]
-    };
-    graphics::draw_rectangle(
-        main_color,
-        0,
-        0,
-        game.get_width(),
-        game.get_height(),
-        con,
-        g,
-    );
-
-    let game_over_msg = if winner && ai {
-        "Green Player Wins!"
-    } else if winner {
-        "Red Player Wins!"
-    } else {
-        "Blue Player Wins!"
+        None
     };
+    window.window.ctx.window().set_fullscreen(monitor);
+}
 
-    let (trans_x, trans_y) = if winner && ai {
-        (140.0, 300.0)
-    } else if winner {
-        (170.0, 300.0)
-    } else {
-        (155.0, 300.0)
-    };
+/// Has each player of the given Game play as the profile with the given index in
+/// `profiles`, or as a guest.
+fn seat_profiles(game: &mut Game, profiles: &[Profile], seats: [Option<usize>; MAX_PLAYERS]) {
+    for (index, seat) in seats.iter().enumerate() {
+        game.set_profile(index, seat.map(|profile| profiles[profile].clone()));
+    }
+}
 
-    //drawing the text on the game over screen
-    text::Text::new_color([1.0, 1.0, 1.0, 1.0], 32)
-        .draw(
-            game_over_msg,
-            glyphs,
-            &con.draw_state.clone(),
-            con.transform.trans(trans_x, trans_y).scale(0.8, 0.8),
-            g,
-        )
-        .unwrap();
-
-    glyphs.factory.encoder.flush(dev);
+/// Waits for a recording to be written and reports where it was saved.
+fn finish_recording(recorder: Recorder) {
+    match recorder.finish() {
+        Ok(path) => println!("Saved the recording to {}", path.display()),
+        Err(err) => eprintln!("Couldn't save the recording: {}", err),
+    }
 }
@@ -0,0 +1,294 @@
+//! This file contains the settings menu, which is drawn over the game and lets the theme, the
+//! rules of the game, the volume and the profile each player plays as be changed without
+//! remembering their keyboard shortcuts.
+
+use crate::audio::AudioSettings;
+use crate::graphics;
+use crate::graphics::{draw_text, Align};
+use crate::profile::Profile;
+use crate::settings::{Settings, TeamMode, TrailMode, MAX_PLAYERS};
+use crate::theme::Theme;
+use input::Key;
+use piston_graphics::character::CharacterCache;
+use piston_graphics::{rectangle, Context, Graphics};
+
+/// The options that can be changed in the settings menu, in the order they are listed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Item {
+    Theme,
+    TrailMode,
+    SuddenDeath,
+    Players,
+    Teams,
+    Spawns,
+    Arena,
+    SmoothMovement,
+    Glow,
+    Volume,
+    Music,
+    Seat(usize), // the profile the player with this index plays as
+}
+
+const ITEMS: [Item; 11] = [
+    Item::Theme,
+    Item::TrailMode,
+    Item::SuddenDeath,
+    Item::Players,
+    Item::Teams,
+    Item::Spawns,
+    Item::Arena,
+    Item::SmoothMovement,
+    Item::Glow,
+    Item::Volume,
+    Item::Music,
+];
+
+/// The font size of the title of the menu.
+const TITLE_SIZE: u32 = 16;
+
+/// The font size of the options in the menu.
+const FONT_SIZE: u32 = 10;
+
+/// The opacity of the background drawn behind the menu.
+const BACKDROP_OPACITY: f32 = 0.85;
+
+/// A SettingsMenu keeps track of the theme, settings and profiles being chosen while the
+/// menu is open, along with which option is selected.  The choices only take effect once the
+/// menu is closed and they are handed back to the Game.
+pub struct SettingsMenu {
+    themes: Vec<Theme>,
+    theme: usize, // the index of the chosen theme in themes
+    settings: Settings,
+    audio: AudioSettings,
+    profiles: Vec<Profile>,
+    seats: [Option<usize>; MAX_PLAYERS], // the index in profiles each player plays as
+    selected: usize,                     // the index of the selected option in items()
+}
+
+impl SettingsMenu {
+    /// Creates a menu choosing between the given themes and profiles, starting from the
+    /// theme with the given index, the given settings and volumes, and the given profile of
+    /// each player.
+    pub fn new(
+        themes: Vec<Theme>,
+        theme: usize,
+        settings: Settings,
+        audio: AudioSettings,
+        profiles: Vec<Profile>,
+        seats: [Option<usize>; MAX_PLAYERS],
+    ) -> Self {
+        SettingsMenu {
+            themes,
+            theme,
+            settings,
+            audio,
+            profiles,
+            seats,
+            selected: 0,
+        }
+    }
+
+    /// Returns the chosen theme.
+    pub fn theme(&self) -> &Theme {
+        &self.themes[self.theme]
+    }
+
+    /// Returns the chosen settings.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Returns the chosen volumes.
+    pub fn audio(&self) -> AudioSettings {
+        self.audio
+    }
+
+    /// Returns the index in the menu's profiles of the profile chosen for each player, or
+    /// None for a guest.
+    pub fn seats(&self) -> [Option<usize>; MAX_PLAYERS] {
+        self.seats
+    }
+
+    /// Returns the options listed in the menu.  A profile can be chosen for each player
+    /// taking part, as long as there are any profiles to choose from.
+    fn items(&self) -> Vec<Item> {
+        let mut items = ITEMS.to_vec();
+        if !self.profiles.is_empty() {
+            let count = self.settings.player_count.min(MAX_PLAYERS);
+            items.extend((0..count).map(Item::Seat));
+        }
+        items
+    }
+
+    /// Handles a key press while the menu is open.  The up and down arrows select an
+    /// option, and the left and right arrows (or return) change it.
+    pub fn key_pressed(&mut self, key: Key) {
+        let count = self.items().len();
+        match key {
+            Key::Up => self.selected = (self.selected + count - 1) % count,
+            Key::Down => self.selected = (self.selected + 1) % count,
+            Key::Left => self.change(false),
+            Key::Right | Key::Return => self.change(true),
+            _ => {}
+        }
+    }
+
+    /// Changes the selected option to its next value, or to its previous value if
+    /// `forward` is false.  Options with only two values are simply toggled.
+    fn change(&mut self, forward: bool) {
+        let item = self.items()[self.selected];
+        let settings = &mut self.settings;
+        match item {
+            Item::Theme => {
+                let step = if forward { 1 } else { self.themes.len() - 1 };
+                self.theme = (self.theme + step) % self.themes.len();
+            }
+            Item::TrailMode => settings.toggle_snake_mode(),
+            Item::SuddenDeath => settings.toggle_sudden_death(),
+            Item::Players => settings.cycle_player_count(forward),
+            Item::Teams => settings.cycle_team_mode(forward),
+            Item::Spawns => settings.random_spawns = !settings.random_spawns,
+            Item::Arena => settings.cycle_arena(forward),
+            Item::SmoothMovement => settings.smooth_movement = !settings.smooth_movement,
+            Item::Glow => settings.glow = !settings.glow,
+            Item::Volume => {
+                // changing the volume unmutes the game, so the change can be heard
+                self.audio.muted = false;
+                self.audio.step_volume(forward);
+            }
+            Item::Music => self.audio.music = !self.audio.music,
+            Item::Seat(index) => self.change_seat(index, forward),
+        }
+        // fewer players leave fewer options, so keep the selection within them
+        self.selected = self.selected.min(self.items().len() - 1);
+    }
+
+    /// Changes the profile the player with the given index plays as to the next (or
+    /// previous) profile that no other player has chosen, going through guest between the
+    /// last profile and the first.
+    fn change_seat(&mut self, index: usize, forward: bool) {
+        // the choices are numbered with 0 for a guest and i + 1 for profile i
+        let choices = self.profiles.len() + 1;
+        let step = if forward { 1 } else { choices - 1 };
+        let mut choice = self.seats[index].map_or(0, |profile| profile + 1);
+        loop {
+            choice = (choice + step) % choices;
+            let profile = choice.checked_sub(1);
+            let taken = profile.is_some()
+                && self
+                    .seats
+                    .iter()
+                    .enumerate()
+                    .any(|(other, &seat)| other != index && seat == profile);
+            if !taken {
+                self.seats[index] = profile;
+                return;
+            }
+        }
+    }
+
+    /// Returns the text describing the given option and its current value.
+    fn describe(&self, item: Item) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        let settings = &self.settings;
+        match item {
+            Item::Theme => format!("Theme: {}", self.theme().name),
+            Item::TrailMode => match settings.trail_mode {
+                TrailMode::Permanent => String::from("Trails: Permanent"),
+                TrailMode::Snake { .. } => String::from("Trails: Snake"),
+            },
+            Item::SuddenDeath => {
+                format!("Sudden death: {}", on_off(settings.sudden_death.is_some()))
+            }
+            Item::Players => format!("Players: {}", settings.player_count),
+            Item::Teams => match settings.team_mode {
+                TeamMode::FreeForAll => String::from("Teams: Free for all"),
+                TeamMode::Teams {
+                    friendly_fire: false,
+                } => String::from("Teams: 2 teams"),
+                TeamMode::Teams {
+                    friendly_fire: true,
+                } => String::from("Teams: 2 teams, friendly fire"),
+            },
+            Item::Spawns if settings.random_spawns => String::from("Spawns: Random"),
+            Item::Spawns => String::from("Spawns: Corners"),
+            Item::Arena => format!("Arena: {}", settings.arena.name()),
+            Item::SmoothMovement => {
+                format!("Smooth movement: {}", on_off(settings.smooth_movement))
+            }
+            Item::Glow => format!("Glow: {}", on_off(settings.glow)),
+            Item::Volume if self.audio.muted => String::from("Volume: Muted"),
+            Item::Volume => format!("Volume: {:.0}%", self.audio.volume * 100.0),
+            Item::Music => format!("Music: {}", on_off(self.audio.music)),
+            Item::Seat(index) => match self.seats[index] {
+                Some(profile) => format!("Player {}: {}", index + 1, self.profiles[profile].name),
+                None => format!("Player {}: Guest", index + 1),
+            },
+        }
+    }
+
+    /// Draws the menu over the area `[x, y, width, height]` (in points), in the colors of
+    /// the chosen theme, so switching themes previews them straight away.  The selected
+    /// option is drawn in the color of player 1 and marked with arrows.
+    pub fn draw<G, C>(&self, area: [f64; 4], con: &Context, g: &mut G, glyphs: &mut C)
+    where
+        G: Graphics,
+        C: CharacterCache<Texture = G::Texture>,
+        C::Error: std::fmt::Debug,
+    {
+        let theme = self.theme();
+        rectangle(
+            graphics::faded(theme.background, BACKDROP_OPACITY),
+            area,
+            con.transform,
+            g,
+        );
+
+        let [x, y, width, height] = area;
+        let line_height = graphics::POINTS_PER_BLOCK;
+        // the title and a blank line, followed by the options and another blank line with the
+        // instructions below it
+        let items = self.items();
+        let lines = items.len() as f64 + 4.0;
+        let top = y + (height - lines * line_height) / 2.0;
+        let line = |index: usize| [x, top + index as f64 * line_height, width, line_height];
+
+        draw_text(
+            "Settings",
+            theme.text,
+            TITLE_SIZE,
+            line(0),
+            Align::Center,
+            con,
+            g,
+            glyphs,
+        );
+        for (index, &item) in items.iter().enumerate() {
+            let (msg, color) = if index == self.selected {
+                (format!("< {} >", self.describe(item)), theme.players[0])
+            } else {
+                (self.describe(item), theme.text)
+            };
+            draw_text(
+                &msg,
+                color,
+                FONT_SIZE,
+                line(index + 2),
+                Align::Center,
+                con,
+                g,
+                glyphs,
+            );
+        }
+        draw_text(
+            "Arrows: choose   Tab: close",
+            graphics::faded(theme.text, 0.7),
+            FONT_SIZE,
+            line(items.len() + 3),
+            Align::Center,
+            con,
+            g,
+            glyphs,
+        );
+    }
+}
@@ -1,11 +1,19 @@
+use crate::event::CollisionKind;
 use crate::game::PLAYER_SPEED;
+use crate::graphics;
 use crate::graphics::Block;
-use piston_window::types::Color;
-use piston_window::{Context, G2d};
-use std::collections::LinkedList;
+use crate::settings::DEFAULT_INPUT_QUEUE;
+use crate::spawn::Spawn;
+use piston_graphics::types::Color;
+use piston_graphics::{Context, Graphics};
+use serde::{Deserialize, Serialize};
+use std::collections::{LinkedList, VecDeque};
+
+/// The color of the arrow drawn on each player's head.
+const HEAD_MARKER_COLOR: Color = [1.0, 1.0, 1.0, 0.85];
 
 /// A simple enumerated type representing the four directions a player can move.
-#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -23,18 +31,37 @@ impl Direction {
             Direction::Right => Direction::Left,
         }
     }
+
+    /// Returns the change in x and y coordinates caused by moving one Block in this direction.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
 }
 
 /// A Player has a current moving direction and a "trail" which is a
 /// LinkedList of the Blocks that it has moved over.
+#[derive(Serialize, Deserialize)]
 pub struct Player {
     moving_direction: Direction, /* the direction in which the player is currently moving */
     has_moved_in_direction: bool, /* true if the player has advanced forward in this direction
                                  since switching to it */
-    backup_direction: Option<Direction>, /* used to allow for quick direction changes */
+    #[serde(default)]
+    queued_directions: VecDeque<Direction>, /* turns waiting to be made, oldest first */
+    #[serde(default = "default_queue_depth")]
+    queue_depth: usize, /* the largest number of turns that can be queued */
 
     trail: LinkedList<Block>, /* head of LL is the front of the player's trail */
-    color: Color,
+    max_length: Option<usize>, /* None if the trail is permanent, Some(n) in snake mode */
+
+    ai: bool,                  /* true if the player is controlled by the AI */
+    crash_site: Option<Block>, /* the Block the player crashed into, once it has crashed */
+    #[serde(default)]
+    crash_kind: Option<CollisionKind>, /* what the player crashed into, once it has crashed */
 
     time_waited: f64, /* time since the player last moved */
 }
@@ -43,8 +70,7 @@ impl Player {
     /// Returns a Player object representing Player 1 at the start of the game.  Player 1
     /// should begin with its head at location (4,3) (i.e., 5 units right and 4 down from the
     /// top-left corner).  It should be oriented (initially moving) to the right, with 2
-    /// additional blocks trailing behind (to the left of) its head.  Player 1 should
-    /// be colored red.  Player 1 will have an initial time_waited value of 0.0
+    /// additional blocks trailing behind (to the left of) its head.
     pub fn player_1() -> Player {
         let mut trail = LinkedList::new();
         // The trail begins as 3 horizontal Blocks with the "head" 5 blocks from the
@@ -53,21 +79,13 @@ impl Player {
         for x in 2..=4 {
             trail.push_front(Block { x, y: 3 });
         }
-        Player {
-            moving_direction: Direction::Right,
-            has_moved_in_direction: false,
-            backup_direction: None,
-            trail,
-            color: piston_window::color::hex("ff0000"), // red
-            time_waited: 0.0,
-        }
+        Player::new(trail, Direction::Right)
     }
 
     /// Returns a Player object representing Player 2 at the start of the game.  Player 2
     /// should begin with its head positioned 4 units left and 5 up from the bottom-right corner.
     /// It should be oriented (initially moving) upward, with 2 additional blocks trailing
-    /// behind (below) its head.  Player 2 should be colored blue.  Player 2 should have an
-    /// initial time_waited value of half of PLAYER_SPEED as defined in the game file.
+    /// behind (below) its head.
     pub fn player_2(game_width: u32, game_height: u32) -> Player {
         let mut trail = LinkedList::new();
         // The trail begins as 3 vertical Blocks with the "head" 5 Blocks up from the
@@ -79,27 +97,160 @@ impl Player {
                 y,
             });
         }
+        Player::new(trail, Direction::Up)
+    }
+
+    /// Returns a Player object representing Player 3 at the start of the game.  Player 3 is
+    /// the mirror image of Player 2 across the vertical center line of the board: its head
+    /// is 3 units right and 5 up from the bottom-left corner and it is moving upward.
+    pub fn player_3(game_height: u32) -> Player {
+        let mut trail = LinkedList::new();
+        for y in (game_height - 5)..=(game_height - 3) {
+            trail.push_back(Block { x: 3, y });
+        }
+        Player::new(trail, Direction::Up)
+    }
+
+    /// Returns a Player object representing Player 4 at the start of the game.  Player 4 is
+    /// the mirror image of Player 1 across the vertical center line of the board: its head
+    /// is 5 units left and 3 down from the top-right corner and it is moving to the left.
+    pub fn player_4(game_width: u32) -> Player {
+        let mut trail = LinkedList::new();
+        for x in (game_width - 5)..=(game_width - 3) {
+            trail.push_back(Block { x, y: 3 });
+        }
+        Player::new(trail, Direction::Left)
+    }
+
+    /// Returns the Player with the given index (0 for Player 1) at the start of a game on a
+    /// board of the given size.  Every player starts with a time_waited value of 0.0, so all
+    /// players move at the same moment.
+    pub fn numbered(index: usize, game_width: u32, game_height: u32) -> Player {
+        match index {
+            0 => Player::player_1(),
+            1 => Player::player_2(game_width, game_height),
+            2 => Player::player_3(game_height),
+            _ => Player::player_4(game_width),
+        }
+    }
+
+    /// Returns a Player starting a round at the given Spawn, with its trail directly behind
+    /// its head.
+    pub fn spawned(spawn: Spawn) -> Player {
+        Player::new(spawn.trail().into_iter().collect(), spawn.direction)
+    }
+
+    /// Creates a human-controlled Player with the given starting trail (head first) and moving
+    /// direction.
+    fn new(trail: LinkedList<Block>, moving_direction: Direction) -> Player {
         Player {
-            moving_direction: Direction::Up,
+            moving_direction,
             has_moved_in_direction: false,
-            backup_direction: None,
+            queued_directions: VecDeque::new(),
+            queue_depth: DEFAULT_INPUT_QUEUE,
             trail,
-            color: piston_window::color::hex("0000ff"), // blue
-            time_waited: PLAYER_SPEED / 2.0,
+            max_length: None,
+            ai: false,
+            crash_site: None,
+            crash_kind: None,
+            time_waited: 0.0,
         }
     }
 
-    /// If the player has a backup direction enqueued, it will make that the player's
-    /// current moving direction and then reset has_moved_in_direction to false and
-    /// its backup direction to None.
+    /// Returns true if the player is controlled by the AI.
+    pub fn is_ai(&self) -> bool {
+        self.ai
+    }
+
+    /// Switches the AI on or off for this player.  Any turns still queued are dropped, so
+    /// neither the AI nor the human player is steered by the other's input.
+    pub fn toggle_ai(&mut self) {
+        self.ai = !self.ai;
+        self.queued_directions.clear();
+    }
+
+    /// Returns true if the player has crashed this round.
+    pub fn has_crashed(&self) -> bool {
+        self.crash_site.is_some()
+    }
+
+    /// Returns the Block the player crashed into, or None if it hasn't crashed.
+    pub fn crash_site(&self) -> Option<Block> {
+        self.crash_site
+    }
+
+    /// Returns what the player crashed into, or None if it hasn't crashed.
+    pub fn crash_kind(&self) -> Option<CollisionKind> {
+        self.crash_kind
+    }
+
+    /// Marks the player as having crashed into the given Block, which holds what the given
+    /// kind of collision describes.  A crashed player no longer moves, but its trail stays on
+    /// the board.
+    pub fn crash(&mut self, site: Block, kind: CollisionKind) {
+        self.crash_site = Some(site);
+        self.crash_kind = Some(kind);
+    }
+
+    /// If the player has any directions queued, makes the oldest one the player's current
+    /// moving direction and resets has_moved_in_direction to false.  Only one queued turn is
+    /// made per move, so every turn in the queue is given a Block of its own.
     pub fn advance_direction_queue(&mut self) {
-        if let Some(dir) = self.backup_direction {
+        if let Some(dir) = self.queued_directions.pop_front() {
             self.moving_direction = dir;
-            self.backup_direction = None;
             self.has_moved_in_direction = false;
         }
     }
 
+    /// Returns the directions the player has queued up, oldest first.
+    pub fn queued_directions(&self) -> impl Iterator<Item = &Direction> {
+        self.queued_directions.iter()
+    }
+
+    /// Sets the largest number of turns that can be queued up while the player hasn't yet
+    /// moved in its current direction.  Turns already queued beyond the new depth are
+    /// dropped, newest first.
+    pub fn set_queue_depth(&mut self, depth: usize) {
+        self.queue_depth = depth;
+        self.queued_directions.truncate(depth);
+    }
+
+    /// Sets the maximum number of Blocks the player's trail may contain.  None makes the
+    /// trail permanent.  If the trail is already longer than the new limit, the oldest Blocks
+    /// are removed until it fits.
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+        self.trim_trail();
+    }
+
+    /// Increases the player's maximum trail length by the given number of Blocks.  This has no
+    /// effect if the player's trail is permanent.
+    pub fn grow(&mut self, blocks: usize) {
+        if let Some(max_length) = self.max_length.as_mut() {
+            *max_length += blocks;
+        }
+    }
+
+    /// Returns the position of the player's head.
+    pub fn head(&self) -> Block {
+        *self.trail.front().unwrap()
+    }
+
+    /// Returns the direction in which the player is currently moving.
+    pub fn moving_direction(&self) -> Direction {
+        self.moving_direction
+    }
+
+    /// Returns the Blocks of the player's trail, starting with its head.
+    pub fn trail(&self) -> impl Iterator<Item = &Block> {
+        self.trail.iter()
+    }
+
+    /// Returns the number of Blocks in the player's trail.
+    pub fn trail_length(&self) -> usize {
+        self.trail.len()
+    }
+
     /// Increments the player's time_waited value by the parameter.
     pub fn wait_time(&mut self, time: f64) {
         self.time_waited += time;
@@ -110,31 +261,124 @@ impl Player {
         self.time_waited
     }
 
-    /// Draws the player given a graphics Context and G2d.  A player is drawn by drawing all
-    /// of the blocks in its trail.  Note that the type Block has a draw() function, which can
-    /// be used here when iterating over the player's trail.
-    pub fn draw(&self, con: &Context, g: &mut G2d, ai: bool) {
-        for block in &self.trail {
-            block.draw(
-                if !ai {
-                    self.color
-                } else {
-                    piston_window::color::hex("00ff00")
-                },
+    /// Draws the player given a graphics Context and Graphics backend.  A player's trail is
+    /// drawn as a path of straight segments joining its turning points (see turning_points),
+    /// which takes one draw call per segment rather than one per Block, in the given Color.
+    /// If `smooth` is true and the player hasn't crashed, the path is extended past the head
+    /// towards the next Block, in proportion to how much of the time until its next move has
+    /// passed, so that the player appears to move continuously.  If `glow` is true, the trail
+    /// is drawn as a thinner line with a soft halo that fades out towards its oldest Block.
+    /// The head is always marked with an arrow pointing in the player's moving direction.
+    pub fn draw<G: Graphics>(
+        &self,
+        color: Color,
+        con: &Context,
+        g: &mut G,
+        smooth: bool,
+        glow: bool,
+    ) {
+        let progress = if smooth && !self.has_crashed() {
+            (self.time_waited / PLAYER_SPEED).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (dx, dy) = self.moving_direction.offset();
+        let head = self.head();
+        let leading_edge = [
+            head.x as f64 + 0.5 + dx as f64 * progress,
+            head.y as f64 + 0.5 + dy as f64 * progress,
+        ];
+
+        let mut points: Vec<[f64; 2]> = self
+            .turning_points()
+            .iter()
+            .map(|block| [block.x as f64 + 0.5, block.y as f64 + 0.5])
+            .collect();
+        if progress > 0.0 {
+            // extend the first segment if the player is still moving along it, otherwise
+            // start a new segment in the player's new direction
+            let first_segment = points.get(1).map(|&[x, y]| {
+                [
+                    ((points[0][0] - x) as i32).signum(),
+                    ((points[0][1] - y) as i32).signum(),
+                ]
+            });
+            if first_segment == Some([dx, dy]) {
+                points[0] = leading_edge;
+            } else {
+                points.insert(0, leading_edge);
+            }
+        }
+
+        if glow {
+            graphics::draw_path(&points, 1.6, |_| graphics::faded(color, 0.2), con, g);
+            let segments = points.len().max(2) as f32 - 1.0;
+            graphics::draw_path(
+                &points,
+                0.5,
+                |segment| graphics::faded(color, 1.0 - 0.6 * segment as f32 / segments),
                 con,
                 g,
             );
+        } else {
+            graphics::draw_path(&points, 1.0, |_| color, con, g);
+        }
+
+        // the head marker sits on the leading edge of the player, so it moves smoothly too
+        graphics::draw_arrow(
+            HEAD_MARKER_COLOR,
+            leading_edge,
+            [dx as f64, dy as f64],
+            con,
+            g,
+        );
+    }
+
+    /// Returns the Blocks of the player's trail at which it changed direction, together with
+    /// its head (first) and the end of its trail (last).  Consecutive points are joined by a
+    /// straight line of trail Blocks.
+    pub fn turning_points(&self) -> Vec<Block> {
+        let mut points = Vec::new();
+        let mut blocks = self.trail.iter();
+        let mut previous = match blocks.next() {
+            Some(&head) => head,
+            None => return points,
+        };
+        points.push(previous);
+
+        let mut last_step = None;
+        for &block in blocks {
+            let step = (
+                block.x as i64 - previous.x as i64,
+                block.y as i64 - previous.y as i64,
+            );
+            if last_step.is_some() && last_step != Some(step) {
+                points.push(previous);
+            }
+            last_step = Some(step);
+            previous = block;
         }
+        if self.trail.len() > 1 {
+            points.push(previous);
+        }
+        points
     }
 
-    /// Moves a player forward by one block.  This should simply extend their trail in their
-    /// moving direction.
+    /// Moves a player forward by one block.  This extends their trail in their moving
+    /// direction, and in snake mode removes the oldest Blocks of the trail if it has grown
+    /// past its maximum length.
     pub fn move_forward(&mut self) {
         self.has_moved_in_direction = true;
         self.trail.push_front(self.next_head_position());
-        if let Some(new_dir) = self.backup_direction {
-            self.moving_direction = new_dir;
-            self.backup_direction = None;
+        self.trim_trail();
+    }
+
+    /// Removes Blocks from the end of the trail until it is no longer than max_length.
+    fn trim_trail(&mut self) {
+        if let Some(max_length) = self.max_length {
+            while self.trail.len() > max_length.max(1) {
+                self.trail.pop_back();
+            }
         }
     }
 
@@ -166,25 +410,43 @@ impl Player {
     }
 
     /// Updates the player's moving direction to the parameter, unless the parameter is None or it
-    /// is the same as the current direction or the opposite of the current direction, or the player
-    /// has not yet moved in that direction.  (e.g., if a player is currently moving Right, then
-    /// this method will only update the player's direction if `Some(Direction::Up)` or
-    /// `Some(Direction::Down)` are passed to the method.)  If the player hasn't moved in their
-    /// current direction yet but the changed direction is still valid, then if the backup direction
-    /// is None it will be set to this direction.
+    /// is the same as the direction the player will be moving in or the opposite of it.  (e.g., if
+    /// a player is moving Right, then this method will only update the player's direction if
+    /// `Some(Direction::Up)` or `Some(Direction::Down)` are passed to the method.)  If the player
+    /// hasn't moved in their current direction yet, the direction is added to the back of the
+    /// player's queue instead, as long as the queue isn't full, and it is checked against the
+    /// last queued direction rather than the current one.  This lets quick sequences of keys,
+    /// like Up then Left for a U-turn, be made one Block at a time.
     pub fn update_direction(&mut self, dir: Option<Direction>) {
         if let Some(direction) = dir {
-            if direction != self.moving_direction
-                && direction != self.moving_direction.opposite_direction()
-            {
-                if self.has_moved_in_direction {
-                    self.moving_direction = direction;
-                    self.has_moved_in_direction = false;
-                } else if self.backup_direction.is_none() {
-                    self.backup_direction = dir;
-                }
+            let last = *self
+                .queued_directions
+                .back()
+                .unwrap_or(&self.moving_direction);
+            if direction == last || direction == last.opposite_direction() {
+                return;
             }
+            if self.has_moved_in_direction && self.queued_directions.is_empty() {
+                self.moving_direction = direction;
+                self.has_moved_in_direction = false;
+            } else if self.queued_directions.len() < self.queue_depth {
+                self.queued_directions.push_back(direction);
+            }
+        }
+    }
+
+    /// Points the player in the given direction before it has started moving, unless that
+    /// would send it straight back into its own trail.
+    pub fn face(&mut self, direction: Direction) {
+        let head = self.head();
+        let (dx, dy) = direction.offset();
+        let ahead = (head.x as i32 + dx, head.y as i32 + dy);
+        let behind = self.trail.iter().nth(1);
+        if behind.is_some_and(|block| (block.x as i32, block.y as i32) == ahead) {
+            return;
         }
+        self.moving_direction = direction;
+        self.queued_directions.clear();
     }
 
     /// Updates ai's moving direction by turning clockwise or counterclockwise.  If the parameter
@@ -273,7 +535,13 @@ impl Player {
     }
 }
 
+/// Returns the queue depth of players saved before turns could be queued up.
+fn default_queue_depth() -> usize {
+    DEFAULT_INPUT_QUEUE
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -304,7 +572,19 @@ mod tests {
         assert_eq!(Direction::Up, player_2.moving_direction);
         assert_eq!(3, player_2.trail.len());
         assert_eq!(&Block { x: 31, y: 20 }, player_2.trail.front().unwrap());
-        assert_eq!(PLAYER_SPEED / 2.0, player_2.time_waited);
+        assert_eq!(0.0, player_2.time_waited);
+    }
+
+    #[test]
+    fn test_players_3_and_4() {
+        let player_3 = Player::player_3(25);
+        assert_eq!(Direction::Up, player_3.moving_direction);
+        assert_eq!(&Block { x: 3, y: 20 }, player_3.trail.front().unwrap());
+
+        let player_4 = Player::numbered(3, 35, 25);
+        assert_eq!(Direction::Left, player_4.moving_direction);
+        assert_eq!(Block { x: 29, y: 3 }, player_4.next_head_position());
+        assert!(!player_4.is_ai() && !player_4.has_crashed());
     }
 
     #[test]
@@ -316,6 +596,52 @@ mod tests {
         assert!(player_1.trail_covers_location(Block { x: 5, y: 3 }));
     }
 
+    #[test]
+    fn test_move_forward_snake() {
+        let mut player_1 = Player::player_1();
+        player_1.set_max_length(Some(3));
+        player_1.move_forward();
+
+        assert_eq!(3, player_1.trail.len());
+        assert!(player_1.trail_covers_location(Block { x: 5, y: 3 }));
+        assert!(!player_1.trail_covers_location(Block { x: 2, y: 3 }));
+
+        player_1.grow(2);
+        player_1.move_forward();
+        player_1.move_forward();
+        player_1.move_forward();
+        assert_eq!(5, player_1.trail.len());
+        assert_eq!(Block { x: 8, y: 3 }, player_1.head());
+    }
+
+    #[test]
+    fn test_turning_points() {
+        let mut player_1 = Player::player_1();
+        assert_eq!(
+            vec![Block { x: 4, y: 3 }, Block { x: 2, y: 3 }],
+            player_1.turning_points()
+        );
+
+        player_1.move_forward();
+        player_1.update_direction(Some(Direction::Down));
+        player_1.move_forward();
+        player_1.move_forward();
+        player_1.update_direction(Some(Direction::Right));
+        player_1.move_forward();
+        assert_eq!(
+            vec![
+                Block { x: 6, y: 5 },
+                Block { x: 5, y: 5 },
+                Block { x: 5, y: 3 },
+                Block { x: 2, y: 3 },
+            ],
+            player_1.turning_points()
+        );
+
+        player_1.set_max_length(Some(1));
+        assert_eq!(vec![Block { x: 6, y: 5 }], player_1.turning_points());
+    }
+
     #[test]
     fn test_next_head_position() {
         let player_2 = Player::player_2(30, 30);
@@ -337,6 +663,49 @@ mod tests {
         assert_eq!(Direction::Up, player_1.moving_direction);
     }
 
+    #[test]
+    fn test_direction_queue() {
+        // a U-turn made within one move
+        let mut player_1 = Player::player_1();
+        player_1.move_forward();
+        player_1.update_direction(Some(Direction::Up));
+        player_1.update_direction(Some(Direction::Left));
+        assert_eq!(Direction::Up, player_1.moving_direction);
+        assert_eq!(
+            vec![Direction::Left],
+            player_1.queued_directions().copied().collect::<Vec<_>>()
+        );
+        player_1.move_forward();
+        player_1.advance_direction_queue();
+        assert_eq!(Direction::Left, player_1.moving_direction);
+        player_1.move_forward();
+        assert_eq!(Block { x: 4, y: 2 }, player_1.head());
+
+        // each queued direction is checked against the one before it
+        let mut player_1 = Player::player_1();
+        player_1.update_direction(Some(Direction::Down));
+        player_1.update_direction(Some(Direction::Up));
+        player_1.update_direction(Some(Direction::Down));
+        player_1.update_direction(Some(Direction::Left));
+        player_1.update_direction(Some(Direction::Up));
+        assert_eq!(
+            vec![Direction::Down, Direction::Left, Direction::Up],
+            player_1.queued_directions().copied().collect::<Vec<_>>()
+        );
+        // the queue is full
+        player_1.update_direction(Some(Direction::Right));
+        assert_eq!(3, player_1.queued_directions().count());
+
+        player_1.set_queue_depth(1);
+        assert_eq!(
+            vec![Direction::Down],
+            player_1.queued_directions().copied().collect::<Vec<_>>()
+        );
+        player_1.set_queue_depth(0);
+        player_1.update_direction(Some(Direction::Down));
+        assert_eq!(0, player_1.queued_directions().count());
+    }
+
     #[test]
     fn test_trail_covers_location() {
         let player_1 = Player::player_1();
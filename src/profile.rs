@@ -0,0 +1,185 @@
+//! This file contains player profiles, which let people play under their own name and color
+//! with the keys they prefer, and the sets of keys players can be steered with.
+
+use crate::player::Direction;
+use crate::settings::MAX_PLAYERS;
+use crate::theme;
+use input::Key;
+use piston_graphics::types::Color;
+use serde::{Deserialize, Serialize};
+
+/// The sets of keys a player can be steered with.  Without profiles, player 1 uses WASD,
+/// player 2 the arrow keys, player 3 IJKL and player 4 the number pad.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Controls {
+    Wasd,
+    Arrows,
+    Ijkl,
+    NumPad,
+}
+
+impl Controls {
+    /// Every set of keys, in the order they are given to players without a preference.
+    pub const ALL: [Controls; MAX_PLAYERS] = [
+        Controls::Wasd,
+        Controls::Arrows,
+        Controls::Ijkl,
+        Controls::NumPad,
+    ];
+
+    /// Returns the keys of this set, in the order up, down, left, right.
+    pub fn keys(self) -> [Key; 4] {
+        match self {
+            Controls::Wasd => [Key::W, Key::S, Key::A, Key::D],
+            Controls::Arrows => [Key::Up, Key::Down, Key::Left, Key::Right],
+            Controls::Ijkl => [Key::I, Key::K, Key::J, Key::L],
+            Controls::NumPad => [Key::NumPad8, Key::NumPad5, Key::NumPad4, Key::NumPad6],
+        }
+    }
+
+    /// Returns the set of keys the given key belongs to and the direction it stands for, or
+    /// None if it isn't a direction key.
+    pub fn of_key(key: Key) -> Option<(Controls, Direction)> {
+        const DIRECTIONS: [Direction; 4] = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        Controls::ALL.iter().find_map(|&controls| {
+            controls
+                .keys()
+                .iter()
+                .position(|&k| k == key)
+                .map(|position| (controls, DIRECTIONS[position]))
+        })
+    }
+}
+
+/// A Profile is a person who plays the game, with the name they are announced by and,
+/// optionally, the color their player is drawn in and the keys they steer with.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub color: Option<Color>, // None to use the color of the theme
+    pub controls: Option<Controls>,
+}
+
+/// The description of a profile in the config file.  The color is given as a hex string such
+/// as "ff0000".
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ProfileSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controls: Option<Controls>,
+}
+
+impl ProfileSpec {
+    /// Builds the Profile described by this spec.  Returns an error if the name is empty or
+    /// the color isn't a valid hex string.
+    pub fn to_profile(&self) -> Result<Profile, String> {
+        if self.name.trim().is_empty() {
+            return Err(String::from("a profile needs a name"));
+        }
+        let color = match &self.color {
+            Some(spec) => Some(theme::parse_hex(spec)?),
+            None => None,
+        };
+        Ok(Profile {
+            name: self.name.clone(),
+            color,
+            controls: self.controls,
+        })
+    }
+}
+
+/// Decides which set of keys steers each player, given the set each player would prefer.
+/// Preferences are granted in player order, and a player whose preference is already taken,
+/// or who has none, gets their usual set if it is free and otherwise the first free set, so
+/// no two players are ever steered by the same keys.
+pub fn assign_controls(preferences: [Option<Controls>; MAX_PLAYERS]) -> [Controls; MAX_PLAYERS] {
+    let mut assigned: [Option<Controls>; MAX_PLAYERS] = [None; MAX_PLAYERS];
+    for (index, preference) in preferences.iter().enumerate() {
+        if let Some(controls) = preference {
+            if !assigned.contains(&Some(*controls)) {
+                assigned[index] = Some(*controls);
+            }
+        }
+    }
+    for index in 0..MAX_PLAYERS {
+        if assigned[index].is_none() {
+            let usual = Controls::ALL[index];
+            let free = |controls: &Controls| !assigned.contains(&Some(*controls));
+            assigned[index] = Some(if free(&usual) {
+                usual
+            } else {
+                *Controls::ALL
+                    .iter()
+                    .find(|controls| free(controls))
+                    .unwrap()
+            });
+        }
+    }
+    assigned.map(Option::unwrap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_key() {
+        assert_eq!(
+            Some((Controls::Ijkl, Direction::Left)),
+            Controls::of_key(Key::J)
+        );
+        assert_eq!(
+            Some((Controls::NumPad, Direction::Up)),
+            Controls::of_key(Key::NumPad8)
+        );
+        assert_eq!(None, Controls::of_key(Key::P));
+    }
+
+    #[test]
+    fn test_assign_controls() {
+        assert_eq!(Controls::ALL, assign_controls([None; MAX_PLAYERS]));
+        // player 2 prefers WASD, so player 1 gets the arrow keys that player 2 would have had
+        let preferences = [None, Some(Controls::Wasd), None, None];
+        assert_eq!(
+            [
+                Controls::Arrows,
+                Controls::Wasd,
+                Controls::Ijkl,
+                Controls::NumPad
+            ],
+            assign_controls(preferences)
+        );
+        // only the first of two players preferring the same keys gets them
+        let preferences = [Some(Controls::NumPad), Some(Controls::NumPad), None, None];
+        assert_eq!(
+            [
+                Controls::NumPad,
+                Controls::Arrows,
+                Controls::Ijkl,
+                Controls::Wasd
+            ],
+            assign_controls(preferences)
+        );
+    }
+
+    #[test]
+    fn test_profile_spec() {
+        let spec = ProfileSpec {
+            name: String::from("Ada"),
+            color: Some(String::from("00ff00")),
+            controls: Some(Controls::Arrows),
+        };
+        let profile = spec.to_profile().unwrap();
+        assert_eq!(Some([0.0, 1.0, 0.0, 1.0]), profile.color);
+        assert_eq!(Some(Controls::Arrows), profile.controls);
+        assert!(ProfileSpec::default().to_profile().is_err());
+    }
+}
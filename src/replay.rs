@@ -0,0 +1,217 @@
+//! This file contains replays, which record a round as it is played so it can be exported
+//! afterwards (see `capture::export_replay`).  A replay holds the state of the game when the
+//! round started, as a saved game (see `save.rs`), followed by every key the game was given
+//! and every update in order.  A Game only changes through those, so playing them back into
+//! the saved game repeats the round exactly, random numbers included.
+
+use crate::event::GameEvent;
+use crate::game::Game;
+use crate::save;
+use input::Key;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The version of the replay format.  It is increased whenever replays change in a way that
+/// older replays can't be read into.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Something that was done to the Game during a recorded round.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// The key was handed to Game::key_pressed.
+    Key(Key),
+    /// The Game was updated by the given number of seconds.
+    Update(f64),
+}
+
+/// A recorded round.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+    start: serde_json::Value, // the saved game the round started from
+    steps: Vec<Step>,
+}
+
+impl Replay {
+    /// Starts a replay of a round starting from the current state of the given Game.
+    pub fn start(game: &Game) -> Result<Replay, String> {
+        Ok(Replay {
+            version: REPLAY_VERSION,
+            start: save::to_value(game)?,
+            steps: Vec::new(),
+        })
+    }
+
+    /// Returns the location replays are saved to by default, `bloxide/last-round.json`
+    /// inside the user's data directory (e.g. `~/.local/share` on Linux), if the platform
+    /// has one.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("bloxide").join("last-round.json"))
+    }
+
+    /// Reads a replay from the file at the given path.  Returns an error if it was saved in a
+    /// different version of the format, or its starting state can't be loaded.
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let replay: Replay = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "the replay was saved in version {} of the format, but only version {} can be \
+                 loaded",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+        replay.game()?;
+        Ok(replay)
+    }
+
+    /// Writes the replay to the file at the given path, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let text = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| err.to_string())
+    }
+
+    /// Returns the Game as it was when the round started, drawn in the classic theme.
+    pub fn game(&self) -> Result<Game, String> {
+        save::from_value(self.start.clone())
+    }
+
+    /// Returns everything that was done to the Game during the round, in order.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+/// Records a replay of every round played in a Game, and saves each one to a file once the
+/// round is over, replacing the last.  Rounds in which a Bot steers a player aren't
+/// recorded, since a script can change while the round is played.
+pub struct ReplayRecorder {
+    path: Option<PathBuf>, // where replays are saved, if the platform has anywhere to save them
+    replay: Option<Replay>, // the replay of the round in progress, if it is being recorded
+}
+
+impl ReplayRecorder {
+    /// Creates a recorder that saves replays to the given path, usually
+    /// Replay::default_path().  Nothing is recorded until the next round starts.
+    pub fn new(path: Option<PathBuf>) -> ReplayRecorder {
+        ReplayRecorder { path, replay: None }
+    }
+
+    /// Records a key that is about to be handed to Game::key_pressed.
+    pub fn key_pressed(&mut self, key: Key) {
+        if let Some(replay) = &mut self.replay {
+            replay.steps.push(Step::Key(key));
+        }
+    }
+
+    /// Records an update of the given Game by the given number of seconds, and must be
+    /// called just before the Game is updated.  If the Game is at the very start of a round,
+    /// a new replay is started from it.
+    pub fn before_update(&mut self, game: &Game, time_elapsed: f64) -> Result<(), String> {
+        let full_countdown = game.settings().countdown as f64;
+        if game.round_time() == 0.0 && game.countdown().unwrap_or(0.0) == full_countdown {
+            self.replay = None;
+            if !game.has_bots() {
+                self.replay = Some(Replay::start(game)?);
+            }
+        }
+        if let Some(replay) = &mut self.replay {
+            replay.steps.push(Step::Update(time_elapsed));
+        }
+        Ok(())
+    }
+
+    /// Saves the replay of the round in progress if the given events, which the Game
+    /// reported since the last update, include the end of the round.
+    pub fn after_update(&mut self, events: &[GameEvent]) -> Result<(), String> {
+        if !events
+            .iter()
+            .any(|event| matches!(event, GameEvent::RoundOver { .. }))
+        {
+            return Ok(());
+        }
+        match self.replay.take() {
+            Some(replay) => {
+                let path = self
+                    .path
+                    .as_ref()
+                    .ok_or("no data directory on this platform")?;
+                replay.save(path)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Stops recording the round in progress, e.g. because the Game was changed some other
+    /// way than through its keys and updates.  Recording starts again with the next round.
+    pub fn stop(&mut self) {
+        self.replay = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER_SPEED;
+
+    #[test]
+    fn test_replay() {
+        let path = std::env::temp_dir().join(format!("bloxide-replay-{}.json", std::process::id()));
+        let mut recorder = ReplayRecorder::new(Some(path.clone()));
+        let mut game = Game::new(35, 25);
+        let play = |game: &mut Game, recorder: &mut ReplayRecorder, key: Option<Key>| {
+            if let Some(key) = key {
+                recorder.key_pressed(key);
+                game.key_pressed(key);
+            }
+            recorder.before_update(game, PLAYER_SPEED / 3.0).unwrap();
+            game.update(PLAYER_SPEED / 3.0);
+            recorder.after_update(&game.take_events()).unwrap();
+        };
+        // player 1 chooses to start downwards, turns twice while moving and then hands over
+        // to the AI, while player 2 turns once
+        play(&mut game, &mut recorder, Some(Key::S));
+        for frame in 0..2000 {
+            let key = match frame {
+                100 => Some(Key::D),
+                120 => Some(Key::W),
+                130 => Some(Key::Up),
+                150 => Some(Key::P),
+                _ => None,
+            };
+            play(&mut game, &mut recorder, key);
+            if game.is_game_over() {
+                break;
+            }
+        }
+        assert!(game.is_game_over());
+
+        // playing the replay back ends the round in exactly the same state
+        let replay = Replay::load(&path).unwrap();
+        let mut replayed = replay.game().unwrap();
+        for step in replay.steps() {
+            match *step {
+                Step::Key(key) => replayed.key_pressed(key),
+                Step::Update(time_elapsed) => replayed.update(time_elapsed),
+            }
+        }
+        assert!(replayed.is_game_over());
+        assert_eq!(save::to_json(&game), save::to_json(&replayed));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rejected_replays() {
+        let path = std::env::temp_dir().join(format!("bloxide-old-{}.json", std::process::id()));
+        let mut replay = Replay::start(&Game::new(35, 25)).unwrap();
+        replay.version = REPLAY_VERSION + 1;
+        replay.save(&path).unwrap();
+        assert!(Replay::load(&path).is_err_and(|err| err.contains("version")));
+        fs::remove_file(&path).unwrap();
+    }
+}
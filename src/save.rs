@@ -0,0 +1,216 @@
+//! This file contains saved games, which store the complete state of a round in progress so
+//! it can be resumed later, or attached to a bug report to reproduce a problem exactly.
+
+use crate::game::Game;
+use crate::graphics::Block;
+use crate::settings::MAX_PLAYERS;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The smallest board a saved game can be played on, in blocks in each direction.
+const MIN_BOARD_SIZE: u32 = 5;
+
+/// The version of the saved game format.  It is increased whenever the state of a Game
+/// changes in a way that older saved games can't be read into, so they are rejected with a
+/// clear message instead of being misread.
+pub const SAVE_VERSION: u32 = 1;
+
+/// The contents of a saved game file as it is written.
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+/// The contents of a saved game file as it is read.  The game is only parsed once the
+/// version is known to match.
+#[derive(Deserialize)]
+struct LoadedFile {
+    version: u32,
+    game: serde_json::Value,
+}
+
+/// Returns the location games are saved to by default, `bloxide/saved-game.json` inside the
+/// user's data directory (e.g. `~/.local/share` on Linux), if the platform has one.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("bloxide").join("saved-game.json"))
+}
+
+/// Writes the state of the given Game to the file at the given path, creating its directory
+/// if needed.
+pub fn save(game: &Game, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(path, to_json(game)?).map_err(|err| err.to_string())
+}
+
+/// Reads a Game from the saved game file at the given path.  The Game is drawn in the
+/// classic theme until it is given another.
+pub fn load(path: &Path) -> Result<Game, String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    from_json(&text)
+}
+
+/// Returns the contents of a saved game file holding the state of the given Game.
+pub fn to_json(game: &Game) -> Result<String, String> {
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        game,
+    };
+    serde_json::to_string_pretty(&file).map_err(|err| err.to_string())
+}
+
+/// Parses a Game from the contents of a saved game file.  Returns an error if the file was
+/// saved in a different version of the format, or describes a board the players don't fit
+/// on.
+pub fn from_json(text: &str) -> Result<Game, String> {
+    from_value(serde_json::from_str(text).map_err(|err| err.to_string())?)
+}
+
+/// Returns the contents of a saved game file holding the state of the given Game, as a JSON
+/// value that can be embedded in another file, such as a replay (see `replay.rs`).
+pub fn to_value(game: &Game) -> Result<serde_json::Value, String> {
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        game,
+    };
+    serde_json::to_value(&file).map_err(|err| err.to_string())
+}
+
+/// Parses a Game from the contents of a saved game file given as a JSON value, with the same
+/// checks as from_json.
+pub fn from_value(value: serde_json::Value) -> Result<Game, String> {
+    let file: LoadedFile = serde_json::from_value(value).map_err(|err| err.to_string())?;
+    if file.version != SAVE_VERSION {
+        return Err(format!(
+            "the game was saved in version {} of the format, but only version {} can be loaded",
+            file.version, SAVE_VERSION
+        ));
+    }
+    let game: Game = serde_json::from_value(file.game).map_err(|err| err.to_string())?;
+    check(&game)?;
+    Ok(game)
+}
+
+/// Checks that a loaded Game can be played, so a damaged or hand-edited file is reported
+/// instead of crashing the game later on.
+fn check(game: &Game) -> Result<(), String> {
+    let (width, height) = (game.get_width(), game.get_height());
+    if width < MIN_BOARD_SIZE || height < MIN_BOARD_SIZE {
+        return Err(format!("a {}x{} board is too small", width, height));
+    }
+    if game.rings_closed() > game.max_rings() {
+        return Err(format!(
+            "{} rings can't be closed on a {}x{} board",
+            game.rings_closed(),
+            width,
+            height
+        ));
+    }
+    let count = game.players().len();
+    if !(2..=MAX_PLAYERS).contains(&count) {
+        return Err(format!("a game can't have {} players", count));
+    }
+    let teams = game.team_count();
+    if let Some(team) = game.winner_index().filter(|&team| team >= teams) {
+        return Err(format!(
+            "team {} can't have won a game of {} teams",
+            team + 1,
+            teams
+        ));
+    }
+    if let Some(team) = (teams..MAX_PLAYERS).find(|&team| game.score(team) > 0) {
+        return Err(format!(
+            "team {} can't have a score in a game of {} teams",
+            team + 1,
+            teams
+        ));
+    }
+    let outside = |block: &Block| block.x >= width || block.y >= height;
+    let on_border = |block: Block| {
+        block.x == 0 || block.y == 0 || block.x == width - 1 || block.y == height - 1
+    };
+    if game.food().as_ref().is_some_and(outside) {
+        return Err(String::from("the food is outside the board"));
+    }
+    if game.obstacles().iter().any(outside) {
+        return Err(String::from("an obstacle is outside the board"));
+    }
+    for (index, player) in game.players().iter().enumerate() {
+        if player.trail_length() == 0 {
+            return Err(format!("player {} has no trail", index + 1));
+        }
+        if player.trail().any(outside) {
+            return Err(format!("player {} is outside the board", index + 1));
+        }
+        if on_border(player.head()) {
+            return Err(format!("player {} has its head in the wall", index + 1));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER_SPEED;
+    use crate::settings::Settings;
+    use input::Key;
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = Game::new(35, 25);
+        game.skip_countdown();
+        game.key_pressed(Key::P);
+        game.update(PLAYER_SPEED * 2.5);
+        game.key_pressed(Key::Down);
+        game.key_pressed(Key::Left);
+        game.update(PLAYER_SPEED * 0.5);
+
+        let json = to_json(&game).unwrap();
+        let mut loaded = from_json(&json).unwrap();
+        assert_eq!(json, to_json(&loaded).unwrap());
+
+        // the resumed game carries on exactly like the original, including the player 2 turn
+        // that was queued up but not yet made
+        for _ in 0..20 {
+            game.update(PLAYER_SPEED);
+            loaded.update(PLAYER_SPEED);
+        }
+        assert_eq!(to_json(&game).unwrap(), to_json(&loaded).unwrap());
+        assert!(loaded.players()[0].is_ai());
+
+        // and goes on drawing the same random numbers, e.g. for the spawns of the next round
+        let settings = Settings {
+            random_spawns: true,
+            ..game.settings()
+        };
+        game.apply_settings(settings);
+        loaded.apply_settings(settings);
+        assert_eq!(to_json(&game).unwrap(), to_json(&loaded).unwrap());
+    }
+
+    #[test]
+    fn test_rejected_files() {
+        let json = to_json(&Game::new(35, 25)).unwrap();
+        let newer = json.replacen("\"version\": 1", "\"version\": 2", 1);
+        assert!(from_json(&newer).is_err_and(|err| err.contains("version 2")));
+        let small = json.replacen("\"width\": 35", "\"width\": 20", 1);
+        assert!(from_json(&small).is_err());
+        let closed = json.replacen("\"rings_closed\": 0", "\"rings_closed\": 11", 1);
+        assert!(from_json(&closed).is_ok());
+        let overclosed = json.replacen("\"rings_closed\": 0", "\"rings_closed\": 12", 1);
+        assert!(from_json(&overclosed).is_err_and(|err| err.contains("12 rings")));
+        let won = json.replacen("\"winner\": null", "\"winner\": 2", 1);
+        assert!(from_json(&won).is_err_and(|err| err.contains("team 3")));
+        let mut scored = to_value(&Game::new(35, 25)).unwrap();
+        scored["game"]["scores"][3] = 1.into();
+        assert!(from_value(scored).is_err_and(|err| err.contains("team 4")));
+        let mut walled = to_value(&Game::new(35, 25)).unwrap();
+        walled["game"]["players"][1]["trail"][0]["y"] = 0.into();
+        assert!(from_value(walled).is_err_and(|err| err.contains("player 2")));
+        assert!(from_json("{}").is_err());
+    }
+}
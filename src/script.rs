@@ -0,0 +1,319 @@
+//! This file contains bots written in Rhai, a small scripting language embedded in the game,
+//! so players can write their own AI.  A script defines a `choose(board)` function, which is
+//! called every time its player is about to move and returns the direction to move in as
+//! "up", "down", "left" or "right".  The script is reloaded whenever its file changes, and is
+//! stopped if it runs for too long, so a broken script can't freeze the game.
+
+use crate::bot::{BoardView, Bot};
+use crate::graphics::Block;
+use crate::player::Direction;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST, INT};
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// The longest a script may run each time its player moves.  A move takes a tenth of a
+/// second, so this leaves the rest of the game plenty of time.
+const TIME_LIMIT: Duration = Duration::from_millis(5);
+
+/// The largest number of operations a script may run each time its player moves, which
+/// stops a runaway script even on a machine fast enough to keep within the time limit.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// The name of the function every script must define.
+const ENTRY_POINT: &str = "choose";
+
+/// A Bot that asks a Rhai script which way to move.  The board is given to the script as a
+/// `Board` with the properties `width`, `height`, `me` (the index of the player being
+/// steered), `players` (an array of maps with `x`, `y`, `direction` and `crashed`) and
+/// `food` (a map with `x` and `y`, or `()` if there is none), and the method
+/// `is_free(x, y)`.
+pub struct ScriptBot {
+    engine: Engine,
+    ast: AST,
+    path: Option<PathBuf>,        // the file the script was loaded from, if any
+    modified: Option<SystemTime>, // when the file was last changed, as of the last reload
+    started: Rc<Cell<Instant>>,   // when the script was last called, checked as it runs
+    error: Option<String>,        // the last error reported, so it is only reported once
+    reload_error: Option<String>, // why the file couldn't be reloaded, until it can be
+}
+
+impl ScriptBot {
+    /// Loads the script in the file at the given path.  Returns an error if the file can't
+    /// be read, or the script doesn't compile or has no `choose(board)` function.
+    pub fn load(path: &Path) -> Result<ScriptBot, String> {
+        let source = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut bot = ScriptBot::from_source(&source)?;
+        bot.path = Some(path.to_path_buf());
+        bot.modified = modified(path);
+        Ok(bot)
+    }
+
+    /// Compiles the given script, which is never reloaded.  Returns an error if the script
+    /// doesn't compile or has no `choose(board)` function.
+    pub fn from_source(source: &str) -> Result<ScriptBot, String> {
+        let started = Rc::new(Cell::new(Instant::now()));
+        let engine = engine(Rc::clone(&started));
+        let ast = compile(&engine, source)?;
+        Ok(ScriptBot {
+            engine,
+            ast,
+            path: None,
+            modified: None,
+            started,
+            error: None,
+            reload_error: None,
+        })
+    }
+
+    /// Returns why the script's file couldn't be reloaded, if it has been changed into
+    /// something that doesn't compile, or else the last error the script ran into, if it
+    /// hasn't run successfully since.
+    pub fn error(&self) -> Option<&str> {
+        self.reload_error.as_deref().or(self.error.as_deref())
+    }
+
+    /// Compiles the script again if its file has changed since it was last loaded.  If the
+    /// new script doesn't compile, the error is reported and the old script is kept.
+    fn reload_if_changed(&mut self) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let modified = modified(&path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        let source = fs::read_to_string(&path).map_err(|err| err.to_string());
+        match source.and_then(|source| compile(&self.engine, &source)) {
+            Ok(ast) => {
+                self.ast = ast;
+                self.reload_error = None;
+                eprintln!("Reloaded {}", path.display());
+            }
+            Err(err) => {
+                let error = format!("Couldn't reload {}: {}", path.display(), err);
+                eprintln!("{}", error);
+                self.reload_error = Some(error);
+            }
+        }
+    }
+
+    /// Prints the given error, unless it is the same as the last one.
+    fn report(&mut self, error: String) {
+        if self.error.as_ref() != Some(&error) {
+            eprintln!("{}", error);
+            self.error = Some(error);
+        }
+    }
+}
+
+impl Bot for ScriptBot {
+    /// Calls the script's `choose` function with the given board.  If the script fails, runs
+    /// for too long or returns something that isn't a direction, the error is reported and
+    /// the move is left to the built-in AI, as it is if the script returns `()`.
+    fn choose(&mut self, board: &BoardView) -> Option<Direction> {
+        self.reload_if_changed();
+        self.started.set(Instant::now());
+        let result = self.engine.call_fn::<Dynamic>(
+            &mut Scope::new(),
+            &self.ast,
+            ENTRY_POINT,
+            (board.clone(),),
+        );
+        let value = match result {
+            Ok(value) => value,
+            Err(err) => {
+                let reason = match *err {
+                    EvalAltResult::ErrorTerminated(..) => {
+                        format!("it ran for longer than {} ms", TIME_LIMIT.as_millis())
+                    }
+                    EvalAltResult::ErrorTooManyOperations(..) => {
+                        format!("it ran more than {} operations", MAX_OPERATIONS)
+                    }
+                    err => err.to_string(),
+                };
+                self.report(format!("The bot script was stopped: {}", reason));
+                return None;
+            }
+        };
+        if value.is_unit() {
+            self.error = None;
+            return None;
+        }
+        let direction = value
+            .clone()
+            .into_immutable_string()
+            .ok()
+            .and_then(|name| direction(&name));
+        match direction {
+            Some(_) => self.error = None,
+            None => self.report(format!("The bot script chose {}, not a direction", value)),
+        }
+        direction
+    }
+}
+
+/// Creates the engine scripts are run with, which stops them once they have run for longer
+/// than `TIME_LIMIT` since the time in `started`, and knows about the `Board` type.
+fn engine(started: Rc<Cell<Instant>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_progress(move |_| {
+        if started.get().elapsed() > TIME_LIMIT {
+            Some(Dynamic::UNIT)
+        } else {
+            None
+        }
+    });
+    engine
+        .register_type_with_name::<BoardView>("Board")
+        .register_get("width", |board: &mut BoardView| board.width() as INT)
+        .register_get("height", |board: &mut BoardView| board.height() as INT)
+        .register_get("me", |board: &mut BoardView| board.me() as INT)
+        .register_get("players", |board: &mut BoardView| {
+            let players = board.players().iter().map(|player| {
+                let mut map = position(player.head);
+                map.insert("direction".into(), name(player.direction).into());
+                map.insert("crashed".into(), player.crashed.into());
+                Dynamic::from_map(map)
+            });
+            players.collect::<Array>()
+        })
+        .register_get("food", |board: &mut BoardView| {
+            board
+                .food()
+                .map_or(Dynamic::UNIT, |food| Dynamic::from_map(position(food)))
+        })
+        .register_fn("is_free", |board: &mut BoardView, x: INT, y: INT| {
+            match (i32::try_from(x), i32::try_from(y)) {
+                (Ok(x), Ok(y)) => board.is_free(x, y),
+                _ => false,
+            }
+        });
+    engine
+}
+
+/// Compiles the given script, and checks that it has a `choose` function taking the board.
+fn compile(engine: &Engine, source: &str) -> Result<AST, String> {
+    let ast = engine.compile(source).map_err(|err| err.to_string())?;
+    if !ast
+        .iter_functions()
+        .any(|function| function.name == ENTRY_POINT && function.params.len() == 1)
+    {
+        return Err(format!("the script has no {}(board) function", ENTRY_POINT));
+    }
+    Ok(ast)
+}
+
+/// Returns when the file at the given path was last changed, if that can be found out.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Returns a map with the `x` and `y` of the given Block.
+fn position(block: Block) -> Map {
+    let mut map = Map::new();
+    map.insert("x".into(), (block.x as INT).into());
+    map.insert("y".into(), (block.y as INT).into());
+    map
+}
+
+/// Returns the name scripts use for the given direction.
+fn name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// Returns the direction with the given name, or None if there is no such direction.
+fn direction(name: &str) -> Option<Direction> {
+    match name {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use std::fs::File;
+
+    /// Goes up if it can, and down otherwise.
+    const UP: &str = r#"
+        fn choose(board) {
+            let me = board.players[board.me];
+            if board.is_free(me.x, me.y - 1) { "up" } else { "down" }
+        }
+    "#;
+
+    #[test]
+    fn test_choose() {
+        let game = Game::new(35, 25);
+        let mut bot = ScriptBot::from_source(UP).unwrap();
+        assert_eq!(Some(Direction::Up), bot.choose(&game.board_view(0)));
+
+        let mut bot = ScriptBot::from_source("fn choose(board) { board.food }").unwrap();
+        assert_eq!(None, bot.choose(&game.board_view(0)));
+        assert_eq!(None, bot.error());
+        let mut bot = ScriptBot::from_source(r#"fn choose(board) { "sideways" }"#).unwrap();
+        assert_eq!(None, bot.choose(&game.board_view(0)));
+        assert!(bot.error().is_some());
+    }
+
+    #[test]
+    fn test_bad_scripts() {
+        assert!(ScriptBot::from_source("fn choose(board) {").is_err());
+        assert!(ScriptBot::from_source("fn steer(board) { \"up\" }").is_err());
+
+        // a script that never returns is stopped
+        let game = Game::new(35, 25);
+        let mut bot = ScriptBot::from_source("fn choose(board) { loop {} }").unwrap();
+        let started = Instant::now();
+        assert_eq!(None, bot.choose(&game.board_view(0)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(bot.error().unwrap().contains("stopped"));
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = std::env::temp_dir().join(format!("bloxide-bot-{}.rhai", std::process::id()));
+        fs::write(&path, UP).unwrap();
+        let mut bot = ScriptBot::load(&path).unwrap();
+        let game = Game::new(35, 25);
+        assert_eq!(Some(Direction::Up), bot.choose(&game.board_view(0)));
+
+        // the file's time is moved on, in case it changes faster than the clock ticks
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::write(&path, r#"fn choose(board) { "down" }"#).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(later))
+            .unwrap();
+        assert_eq!(Some(Direction::Down), bot.choose(&game.board_view(0)));
+
+        // a script that doesn't compile leaves the old one running
+        fs::write(&path, "fn choose(board) {").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(later + Duration::from_secs(5)))
+            .unwrap();
+        assert_eq!(Some(Direction::Down), bot.choose(&game.board_view(0)));
+        assert!(bot.error().is_some());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+//! This file contains the options that change the rules of a round of the game,
+//! such as whether the players' trails are permanent or limited in length, as well
+//! as options for how the game is displayed.
+
+use serde::{Deserialize, Serialize};
+
+/// The largest number of players that can take part in a round.
+pub const MAX_PLAYERS: usize = 4;
+
+/// The number of turns each player can queue up by default while waiting to move.
+pub const DEFAULT_INPUT_QUEUE: usize = 3;
+
+/// Determines how long each player's trail is allowed to become.  In the config file the
+/// variant is given by its `mode` field, e.g. `{ mode = "Snake", max_length = 10, growth = 3 }`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum TrailMode {
+    /// The classic mode: trails are permanent and grow by one Block every move.
+    Permanent,
+    /// Snake mode: trails never grow past `max_length` Blocks, with the oldest Block being
+    /// removed every time the player moves.  If `growth` is non-zero, a food item is placed on
+    /// the board and the player who eats it has its maximum length increased by `growth`.
+    Snake { max_length: usize, growth: usize },
+}
+
+impl TrailMode {
+    /// The trail mode used when snake mode is switched on from the keyboard.
+    pub const DEFAULT_SNAKE: TrailMode = TrailMode::Snake {
+        max_length: 10,
+        growth: 3,
+    };
+
+    /// Returns the maximum trail length for this mode, or None if trails are permanent.
+    pub fn max_length(self) -> Option<usize> {
+        match self {
+            TrailMode::Permanent => None,
+            TrailMode::Snake { max_length, .. } => Some(max_length),
+        }
+    }
+
+    /// Returns true if food items should be placed on the board in this mode.
+    pub fn has_food(self) -> bool {
+        matches!(self, TrailMode::Snake { growth, .. } if growth > 0)
+    }
+}
+
+/// Options for sudden-death mode, in which the playable area shrinks as the round goes on.
+/// After `start_after` ticks the outermost ring of the board becomes part of the wall, and
+/// another ring closes every `interval` ticks after that.  A tick is one `PLAYER_SPEED` step.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SuddenDeath {
+    pub start_after: u32,
+    pub interval: u32,
+}
+
+impl SuddenDeath {
+    /// The sudden-death options used when the mode is switched on from the keyboard.
+    pub const DEFAULT: SuddenDeath = SuddenDeath {
+        start_after: 300,
+        interval: 50,
+    };
+}
+
+/// Determines whether the players play for themselves or in teams.  In the config file the
+/// variant is given by its `mode` field, like a TrailMode.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum TeamMode {
+    /// Every player is on their own, and the last player left wins.
+    FreeForAll,
+    /// Players are split into two teams (see `team::team_of`), and the round is won by the
+    /// team that still has a player left.  If `friendly_fire` is false, players can pass
+    /// through the trails of their teammates.
+    Teams { friendly_fire: bool },
+}
+
+/// Determines which obstacles, if any, are placed on the board at the start of a round.  The
+/// layout is generated from the seed of the round (see `arena::generate`), so it changes
+/// every round but is always the same for the same seed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ArenaStyle {
+    /// No obstacles, just the wall around the board.
+    Open,
+    /// Scattered square pillars.
+    Pillars,
+    /// Four rooms joined by doorways.
+    Rooms,
+    /// A maze of short walls between regularly spaced posts.
+    Maze,
+    /// Short lines mirrored into a symmetric pattern.
+    Pattern,
+}
+
+impl ArenaStyle {
+    /// Every arena style, in the order they are cycled through.
+    pub const ALL: [ArenaStyle; 5] = [
+        ArenaStyle::Open,
+        ArenaStyle::Pillars,
+        ArenaStyle::Rooms,
+        ArenaStyle::Maze,
+        ArenaStyle::Pattern,
+    ];
+
+    /// Returns the name of this style as shown in the menu and the status line.
+    pub fn name(self) -> &'static str {
+        match self {
+            ArenaStyle::Open => "Open",
+            ArenaStyle::Pillars => "Pillars",
+            ArenaStyle::Rooms => "Rooms",
+            ArenaStyle::Maze => "Maze",
+            ArenaStyle::Pattern => "Pattern",
+        }
+    }
+}
+
+/// The collection of options a Game is created with.  `Settings::default()` gives the
+/// classic rules of the game.  Options left out of the config file take their default
+/// values.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub trail_mode: TrailMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sudden_death: Option<SuddenDeath>,
+    pub player_count: usize, // between 2 and `MAX_PLAYERS`
+    pub team_mode: TeamMode,
+    pub smooth_movement: bool, // whether heads glide between blocks instead of jumping
+    pub glow: bool, // whether trails are drawn with a neon glow that fades along the trail
+    pub countdown: u32, // seconds counted down before the players start moving, 0 for none
+    pub random_spawns: bool, // whether players start at random mirrored spots, not in corners
+    pub arena: ArenaStyle,
+    pub input_queue: usize, // the number of turns each player can queue up while waiting to move
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            trail_mode: TrailMode::Permanent,
+            sudden_death: None,
+            player_count: 2,
+            team_mode: TeamMode::FreeForAll,
+            smooth_movement: true,
+            glow: false,
+            countdown: 3,
+            random_spawns: false,
+            arena: ArenaStyle::Open,
+            input_queue: DEFAULT_INPUT_QUEUE,
+        }
+    }
+}
+
+impl Settings {
+    /// Brings values read from a file, which may have been edited by hand, back within the
+    /// limits the game supports, such as a player count between 2 and `MAX_PLAYERS`.
+    pub fn clamp_to_limits(&mut self) {
+        self.player_count = self.player_count.clamp(2, MAX_PLAYERS);
+    }
+
+    /// Switches between permanent trails and the default snake mode.
+    pub fn toggle_snake_mode(&mut self) {
+        self.trail_mode = match self.trail_mode {
+            TrailMode::Permanent => TrailMode::DEFAULT_SNAKE,
+            TrailMode::Snake { .. } => TrailMode::Permanent,
+        };
+    }
+
+    /// Switches the default sudden-death mode on or off.
+    pub fn toggle_sudden_death(&mut self) {
+        self.sudden_death = match self.sudden_death {
+            None => Some(SuddenDeath::DEFAULT),
+            Some(_) => None,
+        };
+    }
+
+    /// Changes the number of players to the next (or, if `forward` is false, the previous)
+    /// number between 2 and `MAX_PLAYERS`, wrapping around at either end.
+    pub fn cycle_player_count(&mut self, forward: bool) {
+        let choices = MAX_PLAYERS - 1;
+        let current = self.player_count.clamp(2, MAX_PLAYERS) - 2;
+        let step = if forward { 1 } else { choices - 1 };
+        self.player_count = (current + step) % choices + 2;
+    }
+
+    /// Changes the team mode to the next (or, if `forward` is false, the previous) of
+    /// free-for-all, teams, and teams with friendly fire, wrapping around at either end.
+    pub fn cycle_team_mode(&mut self, forward: bool) {
+        const MODES: [TeamMode; 3] = [
+            TeamMode::FreeForAll,
+            TeamMode::Teams {
+                friendly_fire: false,
+            },
+            TeamMode::Teams {
+                friendly_fire: true,
+            },
+        ];
+        let current = MODES
+            .iter()
+            .position(|&mode| mode == self.team_mode)
+            .unwrap();
+        let step = if forward { 1 } else { MODES.len() - 1 };
+        self.team_mode = MODES[(current + step) % MODES.len()];
+    }
+
+    /// Changes the arena style to the next (or, if `forward` is false, the previous) one in
+    /// `ArenaStyle::ALL`, wrapping around at either end.
+    pub fn cycle_arena(&mut self, forward: bool) {
+        let styles = ArenaStyle::ALL;
+        let current = styles
+            .iter()
+            .position(|&style| style == self.arena)
+            .unwrap();
+        let step = if forward { 1 } else { styles.len() - 1 };
+        self.arena = styles[(current + step) % styles.len()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_player_count() {
+        let mut settings = Settings::default();
+        settings.cycle_player_count(true);
+        assert_eq!(3, settings.player_count);
+        settings.cycle_player_count(true);
+        settings.cycle_player_count(true);
+        assert_eq!(2, settings.player_count);
+        settings.cycle_player_count(false);
+        assert_eq!(4, settings.player_count);
+    }
+
+    #[test]
+    fn test_cycle_team_mode() {
+        let mut settings = Settings::default();
+        settings.cycle_team_mode(false);
+        assert_eq!(
+            TeamMode::Teams {
+                friendly_fire: true
+            },
+            settings.team_mode
+        );
+        settings.cycle_team_mode(true);
+        assert_eq!(TeamMode::FreeForAll, settings.team_mode);
+    }
+
+    #[test]
+    fn test_cycle_arena() {
+        let mut settings = Settings::default();
+        settings.cycle_arena(false);
+        assert_eq!(ArenaStyle::Pattern, settings.arena);
+        settings.cycle_arena(true);
+        settings.cycle_arena(true);
+        assert_eq!(ArenaStyle::Pillars, settings.arena);
+    }
+}
@@ -0,0 +1,218 @@
+//! This file contains the spawn generator, which picks where the players start a round and
+//! which way they are heading.  The first player's spawn is chosen at random from a seed, and
+//! the others are its mirror images across the center lines of the board, so no player starts
+//! closer to a wall or an opponent than any other.
+
+use crate::graphics::Block;
+use crate::player::Direction;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The number of Blocks in each player's trail at the start of a round, including the head.
+pub const TRAIL_LENGTH: u32 = 3;
+
+/// The smallest number of Blocks between a player's head and any wall.
+const MIN_WALL_DISTANCE: u32 = 3;
+
+/// The number of free Blocks every player must have straight ahead of it, so nobody starts
+/// the round about to crash.
+const MIN_RUN: u32 = 5;
+
+/// The smallest distance between the heads of any two players, counted in Blocks along the
+/// rows and columns of the board.
+const MIN_SEPARATION: u32 = 8;
+
+/// The number of random spawns tried before giving up on a board.
+const MAX_ATTEMPTS: usize = 200;
+
+/// Where a player starts a round: the Block its head is on and the direction it is moving
+/// in.  The rest of its trail lies directly behind the head.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Spawn {
+    pub head: Block,
+    pub direction: Direction,
+}
+
+impl Spawn {
+    /// Returns the Blocks of the trail the player starts with, head first.
+    pub fn trail(self) -> Vec<Block> {
+        let (dx, dy) = self.direction.offset();
+        (0..TRAIL_LENGTH as i32)
+            .map(|step| Block {
+                x: (self.head.x as i32 - dx * step) as u32,
+                y: (self.head.y as i32 - dy * step) as u32,
+            })
+            .collect()
+    }
+
+    /// Returns the mirror image of this spawn used by the player with the given index on a
+    /// board of the given size: player 2 is rotated half a turn around the center of the
+    /// board, player 3 is mirrored across the vertical center line and player 4 across the
+    /// horizontal one.
+    fn mirrored(self, index: usize, width: u32, height: u32) -> Spawn {
+        let Block { x, y } = self.head;
+        let (flip_x, flip_y) = match index {
+            0 => (false, false),
+            1 => (true, true),
+            2 => (true, false),
+            _ => (false, true),
+        };
+        let direction = match self.direction {
+            Direction::Left | Direction::Right if flip_x => self.direction.opposite_direction(),
+            Direction::Up | Direction::Down if flip_y => self.direction.opposite_direction(),
+            direction => direction,
+        };
+        Spawn {
+            head: Block {
+                x: if flip_x { width - 1 - x } else { x },
+                y: if flip_y { height - 1 - y } else { y },
+            },
+            direction,
+        }
+    }
+}
+
+/// Picks the spawns of the given number of players on a board of the given size with the
+/// given obstacles from the given seed, which always gives the same spawns.  Every head is at
+/// least `MIN_WALL_DISTANCE` Blocks from the walls and `MIN_SEPARATION` Blocks from the other
+/// heads, and no player has a trail or an obstacle in front of it.  Returns None if the board
+/// is too small or crowded for the players to be placed fairly.
+pub fn symmetric_spawns(
+    count: usize,
+    width: u32,
+    height: u32,
+    obstacles: &[Block],
+    seed: u64,
+) -> Option<Vec<Spawn>> {
+    if width < 2 * MIN_WALL_DISTANCE + 1 || height < 2 * MIN_WALL_DISTANCE + 1 {
+        return None;
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    const DIRECTIONS: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+    (0..MAX_ATTEMPTS).find_map(|_| {
+        let first = Spawn {
+            head: Block {
+                x: rng.gen_range(MIN_WALL_DISTANCE..width - MIN_WALL_DISTANCE),
+                y: rng.gen_range(MIN_WALL_DISTANCE..height - MIN_WALL_DISTANCE),
+            },
+            direction: DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())],
+        };
+        let spawns: Vec<Spawn> = (0..count)
+            .map(|index| first.mirrored(index, width, height))
+            .collect();
+        if is_fair(&spawns, width, height, obstacles) {
+            Some(spawns)
+        } else {
+            None
+        }
+    })
+}
+
+/// Checks that the given spawns keep their distance from each other and clear of the given
+/// obstacles, and that every player has `MIN_RUN` free Blocks ahead of it before reaching a
+/// wall, a trail or an obstacle.
+fn is_fair(spawns: &[Spawn], width: u32, height: u32, obstacles: &[Block]) -> bool {
+    let distance = |a: Block, b: Block| a.x.abs_diff(b.x) + a.y.abs_diff(b.y);
+    for (index, spawn) in spawns.iter().enumerate() {
+        if spawns[..index]
+            .iter()
+            .any(|other| distance(spawn.head, other.head) < MIN_SEPARATION)
+        {
+            return false;
+        }
+    }
+
+    let trails: Vec<Block> = spawns.iter().flat_map(|spawn| spawn.trail()).collect();
+    if trails.iter().any(|block| obstacles.contains(block)) {
+        return false;
+    }
+    spawns.iter().all(|spawn| {
+        let (dx, dy) = spawn.direction.offset();
+        (1..=MIN_RUN as i32).all(|step| {
+            let x = spawn.head.x as i32 + dx * step;
+            let y = spawn.head.y as i32 + dy * step;
+            // the outermost ring of the board is the wall
+            let inside = x >= 1 && y >= 1 && x < width as i32 - 1 && y < height as i32 - 1;
+            let block = Block {
+                x: x as u32,
+                y: y as u32,
+            };
+            inside && !trails.contains(&block) && !obstacles.contains(&block)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetric_spawns() {
+        let (width, height) = (35, 25);
+        for count in 2..=4 {
+            for seed in 0..50 {
+                let spawns = symmetric_spawns(count, width, height, &[], seed).unwrap();
+                assert_eq!(count, spawns.len());
+                assert_eq!(
+                    spawns,
+                    symmetric_spawns(count, width, height, &[], seed).unwrap()
+                );
+                for spawn in &spawns {
+                    let Block { x, y } = spawn.head;
+                    assert!((MIN_WALL_DISTANCE..width - MIN_WALL_DISTANCE).contains(&x));
+                    assert!((MIN_WALL_DISTANCE..height - MIN_WALL_DISTANCE).contains(&y));
+                }
+                let mut trails: Vec<Block> = spawns.iter().flat_map(|s| s.trail()).collect();
+                trails.sort_by_key(|block| (block.x, block.y));
+                trails.dedup();
+                assert_eq!(count * TRAIL_LENGTH as usize, trails.len());
+            }
+        }
+        // different seeds give different openings
+        let openings: Vec<_> = (0..10)
+            .map(|seed| symmetric_spawns(2, width, height, &[], seed).unwrap()[0])
+            .collect();
+        assert!(openings.iter().any(|&spawn| spawn != openings[0]));
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let spawn = Spawn {
+            head: Block { x: 4, y: 3 },
+            direction: Direction::Right,
+        };
+        assert_eq!(
+            Spawn {
+                head: Block { x: 30, y: 21 },
+                direction: Direction::Left
+            },
+            spawn.mirrored(1, 35, 25)
+        );
+        assert_eq!(
+            Spawn {
+                head: Block { x: 4, y: 21 },
+                direction: Direction::Right
+            },
+            spawn.mirrored(3, 35, 25)
+        );
+        assert_eq!(
+            vec![
+                Block { x: 4, y: 3 },
+                Block { x: 3, y: 3 },
+                Block { x: 2, y: 3 }
+            ],
+            spawn.trail()
+        );
+    }
+
+    #[test]
+    fn test_small_board() {
+        assert_eq!(None, symmetric_spawns(2, 5, 5, &[], 0));
+        assert_eq!(None, symmetric_spawns(4, 9, 9, &[], 0));
+    }
+}
@@ -0,0 +1,469 @@
+//! This file contains the match history, which records every round played on this computer
+//! so statistics such as each player's win rate survive between runs of the game, and the
+//! stats screen that shows them.
+
+use crate::event::{CollisionKind, GameEvent};
+use crate::game::Game;
+use crate::graphics;
+use crate::graphics::{draw_text, Align};
+use crate::settings::Settings;
+use crate::team;
+use crate::theme::Theme;
+use piston_graphics::character::CharacterCache;
+use piston_graphics::{rectangle, Context, Graphics};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of most recent rounds listed on the stats screen.
+const RECENT_ROUNDS: usize = 5;
+
+/// The font size of the title of the stats screen.
+const TITLE_SIZE: u32 = 16;
+
+/// The font size of the text on the stats screen.
+const FONT_SIZE: u32 = 10;
+
+/// The opacity of the background drawn behind the stats screen.
+const BACKDROP_OPACITY: f32 = 0.9;
+
+/// Who was steering a player during a round.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Controller {
+    Human,
+    Ai,
+}
+
+/// How a single player did in a recorded round.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PlayerRecord {
+    pub name: String,
+    pub controller: Controller, // who was steering the player when the round ended
+    pub team: usize,
+    pub trail_length: usize,
+    pub crashed: bool,
+    // what the player crashed into; other players are given by their index in the round's
+    // list of players.  Rounds recorded before causes were kept have None.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crash: Option<CollisionKind>,
+    pub won: bool, // true if the player's team won the round
+}
+
+/// A finished round, as stored in the match history.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RoundRecord {
+    pub finished_at: u64, // seconds since the Unix epoch
+    pub map: String,
+    pub settings: Settings,
+    pub ticks: u32,
+    #[serde(default)]
+    pub seed: u64, // the seed of the game, to play the match again with `--seed`
+    pub winner: Option<String>, // the name of the winning team, None for a draw
+    pub players: Vec<PlayerRecord>,
+}
+
+impl RoundRecord {
+    /// Creates a record of the given Game's round, which should be over.  Players are named
+    /// after their profiles, or after their number (e.g. "Player 1") for guests, and count
+    /// as AI players if the AI was steering them when the round ended.
+    pub fn from_game(game: &Game) -> Self {
+        let mode = game.settings().team_mode;
+        let players = game
+            .players()
+            .iter()
+            .enumerate()
+            .map(|(index, player)| PlayerRecord {
+                name: game.player_name(index),
+                controller: if player.is_ai() {
+                    Controller::Ai
+                } else {
+                    Controller::Human
+                },
+                team: team::team_of(index, mode),
+                trail_length: player.trail_length(),
+                crashed: player.has_crashed(),
+                crash: player.crash_kind(),
+                won: game.winner_index() == Some(team::team_of(index, mode)),
+            })
+            .collect();
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        RoundRecord {
+            finished_at,
+            map: game.map_name(),
+            settings: game.settings(),
+            ticks: game.ticks(),
+            seed: game.seed(),
+            winner: game.winner().map(|team| team.name),
+            players,
+        }
+    }
+
+    /// Returns true if any player on a different team from the given player was steered by
+    /// the AI.
+    fn has_ai_opponent(&self, player: &PlayerRecord) -> bool {
+        self.players
+            .iter()
+            .any(|other| other.team != player.team && other.controller == Controller::Ai)
+    }
+}
+
+/// The win record of one player across the match history, and what the player crashed into.
+/// Rounds in which the player was steered by the AI are counted under the name "AI" instead
+/// of the player's own name.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PlayerStats {
+    pub name: String,
+    pub played: u32,
+    pub won: u32,
+    pub played_vs_ai: u32, // rounds with at least one AI opponent
+    pub won_vs_ai: u32,
+    pub wall_crashes: u32,     // crashes into the wall or an obstacle
+    pub self_crashes: u32,     // crashes into the player's own trail
+    pub opponent_crashes: u32, // crashes into another player's trail or head
+}
+
+impl PlayerStats {
+    /// Returns the fraction of rounds won, or None if no rounds were played.
+    pub fn win_rate(&self) -> Option<f64> {
+        win_rate(self.won, self.played)
+    }
+
+    /// Returns the fraction of rounds against the AI that were won, or None if there were
+    /// no such rounds.
+    pub fn win_rate_vs_ai(&self) -> Option<f64> {
+        win_rate(self.won_vs_ai, self.played_vs_ai)
+    }
+}
+
+/// Returns `won / played`, or None if `played` is zero.
+fn win_rate(won: u32, played: u32) -> Option<f64> {
+    if played == 0 {
+        None
+    } else {
+        Some(won as f64 / played as f64)
+    }
+}
+
+/// The match history: every round recorded so far, oldest first.  It is stored in
+/// `bloxide/history.jsonl` in the user's data directory with one JSON object per line, so
+/// recording a round only has to append a line to the file.
+#[derive(Default)]
+pub struct History {
+    rounds: Vec<RoundRecord>,
+}
+
+impl History {
+    /// Returns the location of the history file, `bloxide/history.jsonl` inside the user's
+    /// data directory (e.g. `~/.local/share` on Linux), if the platform has one.
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("bloxide").join("history.jsonl"))
+    }
+
+    /// Loads the match history.  If there is no history file the history is empty, and any
+    /// line of the file that can't be parsed is reported and skipped, so one damaged record
+    /// doesn't lose the rest.
+    pub fn load() -> History {
+        let path = match History::path() {
+            Some(path) if path.exists() => path,
+            _ => return History::default(),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => History::parse(&text),
+            Err(err) => {
+                eprintln!("Ignoring history file {}: {}", path.display(), err);
+                History::default()
+            }
+        }
+    }
+
+    /// Parses a history from the contents of a history file.
+    fn parse(text: &str) -> History {
+        let rounds = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(number, line)| {
+                serde_json::from_str(line)
+                    .map_err(|err| {
+                        eprintln!("Skipping round {} of the history: {}", number + 1, err)
+                    })
+                    .ok()
+            })
+            .collect();
+        History { rounds }
+    }
+
+    /// Returns every recorded round, oldest first.
+    pub fn rounds(&self) -> &[RoundRecord] {
+        &self.rounds
+    }
+
+    /// Adds a round to the history and appends it to the history file, creating the file and
+    /// its directory if needed.  The round is kept in memory even if it can't be saved.
+    pub fn record(&mut self, round: RoundRecord) -> Result<(), String> {
+        let line = serde_json::to_string(&round).map_err(|err| err.to_string())?;
+        self.rounds.push(round);
+
+        let path = History::path().ok_or("no data directory on this platform")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|err| err.to_string())
+    }
+
+    /// Records the round of the given Game if the given events, which the Game reported
+    /// since the last update, include the end of the round.
+    pub fn update(&mut self, game: &Game, events: &[GameEvent]) -> Result<(), String> {
+        if events
+            .iter()
+            .any(|event| matches!(event, GameEvent::RoundOver { .. }))
+        {
+            self.record(RoundRecord::from_game(game))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the win record and crashes of every player in the history, sorted by name.
+    pub fn player_stats(&self) -> Vec<PlayerStats> {
+        let mut stats: BTreeMap<&str, PlayerStats> = BTreeMap::new();
+        for round in &self.rounds {
+            for player in &round.players {
+                let name = match player.controller {
+                    Controller::Human => player.name.as_str(),
+                    Controller::Ai => "AI",
+                };
+                let entry = stats.entry(name).or_insert_with(|| PlayerStats {
+                    name: name.to_string(),
+                    ..PlayerStats::default()
+                });
+                entry.played += 1;
+                entry.won += player.won as u32;
+                if round.has_ai_opponent(player) {
+                    entry.played_vs_ai += 1;
+                    entry.won_vs_ai += player.won as u32;
+                }
+                match player.crash {
+                    Some(CollisionKind::Wall | CollisionKind::Obstacle) => entry.wall_crashes += 1,
+                    Some(CollisionKind::SelfTrail) => entry.self_crashes += 1,
+                    Some(CollisionKind::OpponentTrail(_) | CollisionKind::HeadOn(_)) => {
+                        entry.opponent_crashes += 1
+                    }
+                    None => {}
+                }
+            }
+        }
+        stats.into_values().collect()
+    }
+
+    /// Draws the stats screen over the area `[x, y, width, height]` (in points), in the
+    /// colors of the given theme.  It shows each player's win rate overall and against the
+    /// AI and how often they crashed into walls, themselves and opponents, followed by the
+    /// most recent rounds.
+    pub fn draw<G, C>(
+        &self,
+        theme: &Theme,
+        area: [f64; 4],
+        con: &Context,
+        g: &mut G,
+        glyphs: &mut C,
+    ) where
+        G: Graphics,
+        C: CharacterCache<Texture = G::Texture>,
+        C::Error: std::fmt::Debug,
+    {
+        rectangle(
+            graphics::faded(theme.background, BACKDROP_OPACITY),
+            area,
+            con.transform,
+            g,
+        );
+
+        let percent = |rate: Option<f64>| {
+            rate.map_or(String::from("-"), |rate| format!("{:.0}%", rate * 100.0))
+        };
+        let mut lines = vec![(
+            format!(
+                "{:<10}{:>7}{:>6}{:>6}{:>8}{:>6}{:>6}{:>6}",
+                "", "Played", "Won", "Win", "vs AI", "Wall", "Self", "Opp"
+            ),
+            theme.text,
+        )];
+        for stats in self.player_stats() {
+            lines.push((
+                format!(
+                    "{:<10}{:>7}{:>6}{:>6}{:>8}{:>6}{:>6}{:>6}",
+                    stats.name,
+                    stats.played,
+                    stats.won,
+                    percent(stats.win_rate()),
+                    percent(stats.win_rate_vs_ai()),
+                    stats.wall_crashes,
+                    stats.self_crashes,
+                    stats.opponent_crashes
+                ),
+                theme.text,
+            ));
+        }
+        if self.rounds.is_empty() {
+            lines.push((String::from("No rounds played yet"), theme.text));
+        }
+        lines.push((String::new(), theme.text));
+        lines.push((String::from("Recent rounds"), theme.text));
+        for round in self.rounds.iter().rev().take(RECENT_ROUNDS) {
+            let result = match &round.winner {
+                Some(winner) => format!("{} won", winner),
+                None => String::from("Draw"),
+            };
+            lines.push((
+                format!(
+                    "{} in {} ticks on {}, seed {}",
+                    result, round.ticks, round.map, round.seed
+                ),
+                graphics::faded(theme.text, 0.7),
+            ));
+        }
+
+        let [x, y, width, height] = area;
+        let line_height = graphics::POINTS_PER_BLOCK;
+        // the title and a blank line, followed by the lines and another blank line with the
+        // instructions below it
+        let count = lines.len() as f64 + 4.0;
+        let top = y + (height - count * line_height).max(0.0) / 2.0;
+        let line = |index: usize| [x, top + index as f64 * line_height, width, line_height];
+
+        draw_text(
+            "Statistics",
+            theme.text,
+            TITLE_SIZE,
+            line(0),
+            Align::Center,
+            con,
+            g,
+            glyphs,
+        );
+        for (index, (text, color)) in lines.iter().enumerate() {
+            draw_text(
+                text,
+                *color,
+                FONT_SIZE,
+                line(index + 2),
+                Align::Center,
+                con,
+                g,
+                glyphs,
+            );
+        }
+        draw_text(
+            "H: close",
+            graphics::faded(theme.text, 0.7),
+            FONT_SIZE,
+            line(lines.len() + 3),
+            Align::Center,
+            con,
+            g,
+            glyphs,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays a round of the given Game until it is over, steering every player with the AI.
+    fn play_out(game: &mut Game) {
+        for index in 0..game.players().len() {
+            game.set_ai(index, true);
+        }
+        while !game.is_game_over() {
+            game.update(0.05);
+        }
+    }
+
+    #[test]
+    fn test_from_game() {
+        let mut game = Game::new(35, 25);
+        play_out(&mut game);
+        let round = RoundRecord::from_game(&game);
+        assert_eq!(2, round.players.len());
+        assert_eq!("Player 1", round.players[0].name);
+        assert_eq!(Controller::Ai, round.players[1].controller);
+        assert_eq!(game.ticks(), round.ticks);
+        assert_eq!(game.seed(), round.seed);
+        assert_eq!(game.winner().map(|team| team.name), round.winner);
+        let winners = round.players.iter().filter(|player| player.won).count();
+        assert_eq!(round.winner.is_some() as usize, winners);
+        for (index, player) in round.players.iter().enumerate() {
+            assert_eq!(game.players()[index].crash_kind(), player.crash);
+            assert_eq!(player.crashed, player.crash.is_some());
+        }
+    }
+
+    #[test]
+    fn test_parse_and_stats() {
+        let player = |name: &str, controller, team, won: bool| PlayerRecord {
+            name: name.to_string(),
+            controller,
+            team,
+            trail_length: 10,
+            crashed: !won,
+            crash: if won { None } else { Some(CollisionKind::Wall) },
+            won,
+        };
+        let round = |players| RoundRecord {
+            finished_at: 0,
+            map: String::from("Open 35x25"),
+            settings: Settings::default(),
+            ticks: 100,
+            seed: 7,
+            winner: None,
+            players,
+        };
+        let rounds = [
+            round(vec![
+                player("Player 1", Controller::Human, 0, true),
+                player("Player 2", Controller::Ai, 1, false),
+            ]),
+            round(vec![
+                player("Player 1", Controller::Human, 0, false),
+                player("Player 2", Controller::Human, 1, true),
+            ]),
+        ];
+        let mut text: String = rounds
+            .iter()
+            .map(|round| serde_json::to_string(round).unwrap() + "\n")
+            .collect();
+        text.push_str("not a round\n\n");
+
+        let history = History::parse(&text);
+        assert_eq!(&rounds[..], history.rounds());
+        let stats = history.player_stats();
+        let names: Vec<&str> = stats.iter().map(|stats| stats.name.as_str()).collect();
+        assert_eq!(vec!["AI", "Player 1", "Player 2"], names);
+        assert_eq!((2, 1), (stats[1].played, stats[1].won));
+        assert_eq!(Some(0.5), stats[1].win_rate());
+        assert_eq!(Some(1.0), stats[1].win_rate_vs_ai());
+        assert_eq!(None, stats[2].win_rate_vs_ai());
+        assert_eq!(Some(0.0), stats[0].win_rate());
+        assert_eq!((1, 0), (stats[1].wall_crashes, stats[1].opponent_crashes));
+
+        // rounds recorded before crash causes were kept still load
+        let old = r#"{"name":"Ada","controller":"human","team":0,"trail_length":3,"crashed":true,"won":false}"#;
+        assert_eq!(
+            None,
+            serde_json::from_str::<PlayerRecord>(old).unwrap().crash
+        );
+    }
+}
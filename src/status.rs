@@ -0,0 +1,184 @@
+//! This file contains the text shown to the players around the board: the heads-up display
+//! below it, the countdown before a round starts and the result once it is over.  It only
+//! decides what the text says and which color it is in, not where it goes or how it is
+//! drawn, so the window, the terminal and the browser all show the same information and each
+//! lays it out in its own way.
+
+use crate::game::{Game, PLAYER_SPEED};
+use piston_graphics::types::Color;
+
+/// The height of the heads-up display, in blocks.
+pub const HUD_HEIGHT: u32 = 3;
+
+/// The font size of the text in the heads-up display and of the crash messages.
+pub const FONT_SIZE: u32 = 10;
+
+/// The font size of the countdown shown in the middle of the board before a round starts.
+pub const COUNTDOWN_SIZE: u32 = 60;
+
+/// The font size of the result shown in the middle of the board once the round is over.
+pub const RESULT_SIZE: u32 = 26;
+
+/// The number of columns the second and third rows of the display are split into.
+pub const COLUMNS: usize = 4;
+
+/// A line of text shown to the players, and the color it is shown in.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Text {
+    pub text: String,
+    pub color: Color,
+}
+
+impl Text {
+    fn new(text: String, color: Color) -> Text {
+        Text { text, color }
+    }
+}
+
+/// The contents of the heads-up display.  The first row shows the `status` on the left and,
+/// in sudden death, the `sudden_death` countdown on the right, the second row shows one of
+/// the `players` per column, and the third row one of the `scores` per column.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hud {
+    pub status: Text,
+    pub sudden_death: Option<Text>,
+    pub players: Vec<Text>,
+    pub scores: Vec<Text>,
+}
+
+/// What is shown over the board once the round is over: the board is tinted in `tint`, with
+/// the `result` in the middle and what each crashed player ran into listed below it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameOver {
+    pub tint: Color,
+    pub result: Text,
+    pub crashes: Vec<Text>,
+}
+
+/// Returns the contents of the heads-up display for the given Game.  The status shows the
+/// round timer, the speed of the players and the game's random seed, and in sudden death
+/// there is also the time until the next ring closes.  Each player is shown with its trail
+/// length and whether it is controlled by the AI or has crashed, in its team's color, and
+/// each team with how many rounds it has won.
+pub fn hud(game: &Game) -> Hud {
+    let text = game.theme().text;
+    let status = format!(
+        "Time {:.1}s   Speed {:.0}/s   Seed {}",
+        game.round_time(),
+        1.0 / PLAYER_SPEED,
+        game.seed()
+    );
+    let sudden_death = game
+        .sudden_death_countdown()
+        .map(|seconds| Text::new(format!("Sudden death {:.1}s", seconds), text));
+
+    let players = game
+        .players()
+        .iter()
+        .enumerate()
+        .map(|(index, player)| {
+            let mut info = format!("{} {}", game.player_label(index), player.trail_length());
+            if player.is_ai() {
+                info.push_str(" AI");
+            }
+            if player.has_crashed() {
+                info.push_str(" X");
+            }
+            Text::new(info, game.team_of_player(index).color)
+        })
+        .collect();
+
+    let scores = (0..game.team_count())
+        .map(|index| {
+            let team = game.team(index);
+            let name = team.name.split(' ').next().unwrap();
+            Text::new(format!("{} {}", name, game.score(index)), team.color)
+        })
+        .collect();
+
+    Hud {
+        status: Text::new(status, text),
+        sudden_death,
+        players,
+        scores,
+    }
+}
+
+/// Returns the countdown shown in the middle of the board before the round starts, or None
+/// once the players are moving.
+pub fn countdown(game: &Game) -> Option<Text> {
+    game.countdown()
+        .map(|seconds| Text::new(format!("{}", seconds.ceil()), game.theme().text))
+}
+
+/// Returns what is shown over the board once the round is over, or None while it is still
+/// going.  The board is tinted in the winning team's color, or grey if the round ended in a
+/// draw.
+pub fn game_over(game: &Game) -> Option<GameOver> {
+    if !game.is_game_over() {
+        return None;
+    }
+    let text = game.theme().text;
+    let tint = game
+        .winner()
+        .map_or([0.5, 0.5, 0.5, 1.0], |team| team.color);
+    let crashes = (0..game.players().len())
+        .filter_map(|index| game.crash_message(index))
+        .map(|message| Text::new(message, text))
+        .collect();
+    Some(GameOver {
+        tint,
+        result: Text::new(game.result_message(), text),
+        crashes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{Settings, SuddenDeath};
+
+    #[test]
+    fn test_hud() {
+        let game = Game::new(35, 25);
+        let display = hud(&game);
+        assert_eq!(
+            format!("Time 0.0s   Speed 10/s   Seed {}", game.seed()),
+            display.status.text
+        );
+        assert_eq!(None, display.sudden_death);
+        assert_eq!(game.players().len(), display.players.len());
+        assert!(display.players[0].text.starts_with(&game.player_label(0)));
+        assert_eq!(game.team_of_player(1).color, display.players[1].color);
+        assert_eq!(game.team_count(), display.scores.len());
+        assert!(display.scores[0].text.ends_with(" 0"));
+
+        let settings = Settings {
+            sudden_death: Some(SuddenDeath {
+                start_after: 2,
+                interval: 3,
+            }),
+            ..Settings::default()
+        };
+        let mut game = Game::with_settings(35, 25, settings);
+        game.skip_countdown();
+        assert_eq!("Sudden death 0.2s", hud(&game).sudden_death.unwrap().text);
+    }
+
+    #[test]
+    fn test_countdown_and_game_over() {
+        let mut game = Game::new(35, 25);
+        assert_eq!("3", countdown(&game).unwrap().text);
+        assert_eq!(None, game_over(&game));
+
+        // with nobody steering, every player drives into a wall and the round ends
+        game.skip_countdown();
+        assert_eq!(None, countdown(&game));
+        while !game.is_game_over() {
+            game.update(PLAYER_SPEED);
+        }
+        let screen = game_over(&game).unwrap();
+        assert_eq!(game.result_message(), screen.result.text);
+        assert!(!screen.crashes.is_empty());
+    }
+}
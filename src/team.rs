@@ -0,0 +1,70 @@
+//! This file contains the names and colors of the sides a round can be won by, and which
+//! side each player plays for under the current TeamMode.
+
+use crate::settings::TeamMode;
+use crate::theme::Theme;
+use piston_graphics::types::Color;
+
+/// A Team is one of the sides that can win a round.  In free-for-all mode every player is
+/// its own Team.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Team {
+    pub name: String,
+    pub color: Color,
+}
+
+/// The number of teams players are split into in team mode.
+const TEAM_COUNT: usize = 2;
+
+/// Returns the index of the team that the player with the given index plays for.  In team
+/// mode players alternate between the two teams, so players 1 and 3 play against players
+/// 2 and 4.
+pub fn team_of(player: usize, mode: TeamMode) -> usize {
+    match mode {
+        TeamMode::FreeForAll => player,
+        TeamMode::Teams { .. } => player % TEAM_COUNT,
+    }
+}
+
+/// Returns the Team with the given index under the given TeamMode.  Teams are named and
+/// colored after the player colors of the theme: in free-for-all mode the team with index i
+/// is player i + 1 (e.g. "Red Player"), and in team mode the two teams take the colors of
+/// players 1 and 2 (e.g. "Red Team").
+pub fn team(index: usize, mode: TeamMode, theme: &Theme) -> Team {
+    let suffix = match mode {
+        TeamMode::FreeForAll => "Player",
+        TeamMode::Teams { .. } => "Team",
+    };
+    Team {
+        name: format!("{} {}", theme.player_names[index], suffix),
+        color: theme.players[index],
+    }
+}
+
+/// Returns the side representing player 1 while it is controlled by the AI in free-for-all
+/// mode.
+pub fn ai_player(theme: &Theme) -> Team {
+    Team {
+        name: format!("{} Player", theme.ai_name),
+        color: theme.ai,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_team_of() {
+        let teams = TeamMode::Teams {
+            friendly_fire: false,
+        };
+        let theme = Theme::classic();
+        assert_eq!(2, team_of(2, TeamMode::FreeForAll));
+        assert_eq!(0, team_of(2, teams));
+        assert_eq!(1, team_of(3, teams));
+        assert_eq!("Blue Team", team(team_of(3, teams), teams, &theme).name);
+        assert_eq!("Orange Player", team(3, TeamMode::FreeForAll, &theme).name);
+        assert_eq!("Green Player", ai_player(&theme).name);
+    }
+}
@@ -0,0 +1,223 @@
+//! This file contains the color themes the game can be drawn in, including palettes that
+//! stay readable for players with red-green color blindness, and the description of a
+//! custom theme that can be given in the config file.
+
+use crate::settings::MAX_PLAYERS;
+use piston_graphics::color::hex;
+use piston_graphics::types::Color;
+use serde::{Deserialize, Serialize};
+
+/// A Theme holds every color used to draw the game, along with the names of the player
+/// colors, which are used to name the players (e.g. "Red Player").
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub border: Color,
+    pub hud: Color,  // the background of the heads-up display
+    pub text: Color, // the color of text drawn on the background or the heads-up display
+    pub food: Color,
+    pub players: [Color; MAX_PLAYERS],
+    pub player_names: [String; MAX_PLAYERS],
+    pub ai: Color, // the color of player 1 while it is controlled by the AI
+    pub ai_name: String,
+    pub overlay_opacity: f32, // the opacity of the winner's color over the board when a round ends
+}
+
+impl Theme {
+    /// The original colors of the game: red, blue, purple and orange players on a dark grey
+    /// board, with the AI drawn in green.
+    pub fn classic() -> Theme {
+        Theme {
+            name: String::from("Classic"),
+            background: [0.2, 0.2, 0.2, 1.0],
+            border: [0.0, 0.0, 0.0, 1.0],
+            hud: [0.1, 0.1, 0.1, 1.0],
+            text: [1.0, 1.0, 1.0, 1.0],
+            food: hex("ffd900"),
+            players: [hex("ff0000"), hex("0000ff"), hex("bf00ff"), hex("ff8c00")],
+            player_names: names(["Red", "Blue", "Purple", "Orange"]),
+            ai: hex("00ff00"),
+            ai_name: String::from("Green"),
+            overlay_opacity: 0.15,
+        }
+    }
+
+    /// A palette for deuteranopia (reduced sensitivity to green), taken from the Okabe-Ito
+    /// colors.  No two players differ only in how red or green they are.
+    pub fn deuteranopia() -> Theme {
+        Theme {
+            name: String::from("Deuteranopia"),
+            players: [hex("e69f00"), hex("0072b2"), hex("cc79a7"), hex("56b4e9")],
+            player_names: names(["Orange", "Blue", "Pink", "Sky"]),
+            ai: hex("f0f0f0"),
+            ai_name: String::from("White"),
+            food: hex("f0e442"),
+            ..Theme::classic()
+        }
+    }
+
+    /// A palette for protanopia (reduced sensitivity to red).  Reds look dark to protanopes,
+    /// so this palette avoids red and uses colors that differ in how blue or yellow they are.
+    pub fn protanopia() -> Theme {
+        Theme {
+            name: String::from("Protanopia"),
+            players: [hex("f0e442"), hex("0072b2"), hex("56b4e9"), hex("e69f00")],
+            player_names: names(["Yellow", "Blue", "Sky", "Orange"]),
+            ai: hex("f0f0f0"),
+            ai_name: String::from("White"),
+            food: hex("cc79a7"),
+            ..Theme::classic()
+        }
+    }
+
+    /// Saturated colors on a black board with a white border, for maximum contrast.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: String::from("High contrast"),
+            background: [0.0, 0.0, 0.0, 1.0],
+            border: [1.0, 1.0, 1.0, 1.0],
+            hud: [0.0, 0.0, 0.0, 1.0],
+            text: [1.0, 1.0, 1.0, 1.0],
+            food: hex("ff8000"),
+            players: [hex("ffff00"), hex("00ffff"), hex("ff00ff"), hex("ffffff")],
+            player_names: names(["Yellow", "Cyan", "Magenta", "White"]),
+            ai: hex("00ff00"),
+            ai_name: String::from("Green"),
+            overlay_opacity: 0.3,
+        }
+    }
+
+    /// Returns all of the built-in themes, starting with the classic theme.
+    pub fn built_in() -> Vec<Theme> {
+        vec![
+            Theme::classic(),
+            Theme::deuteranopia(),
+            Theme::protanopia(),
+            Theme::high_contrast(),
+        ]
+    }
+}
+
+/// The description of a custom theme in the config file.  Colors are given as hex strings
+/// such as "ff0000", and any field that is left out keeps its value from the classic theme.
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hud: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub players: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player_names: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overlay_opacity: Option<f32>,
+}
+
+impl ThemeSpec {
+    /// Builds the Theme described by this spec.  Returns an error naming the first color that
+    /// isn't a valid hex string.
+    pub fn to_theme(&self) -> Result<Theme, String> {
+        let mut theme = Theme::classic();
+        theme.name = self.name.clone().unwrap_or_else(|| String::from("Custom"));
+
+        let colors = [
+            (&self.background, &mut theme.background),
+            (&self.border, &mut theme.border),
+            (&self.hud, &mut theme.hud),
+            (&self.text, &mut theme.text),
+            (&self.food, &mut theme.food),
+            (&self.ai, &mut theme.ai),
+        ];
+        for (spec, color) in colors {
+            if let Some(spec) = spec {
+                *color = parse_hex(spec)?;
+            }
+        }
+        for (spec, color) in self.players.iter().flatten().zip(theme.players.iter_mut()) {
+            *color = parse_hex(spec)?;
+        }
+        for (spec, name) in self
+            .player_names
+            .iter()
+            .flatten()
+            .zip(theme.player_names.iter_mut())
+        {
+            *name = spec.clone();
+        }
+        if let Some(ai_name) = &self.ai_name {
+            theme.ai_name = ai_name.clone();
+        }
+        if let Some(opacity) = self.overlay_opacity {
+            theme.overlay_opacity = opacity.clamp(0.0, 1.0);
+        }
+        Ok(theme)
+    }
+}
+
+/// Converts an array of color names into the Strings a Theme holds.
+fn names(names: [&str; MAX_PLAYERS]) -> [String; MAX_PLAYERS] {
+    names.map(String::from)
+}
+
+/// Parses a color given as a hex string of 6 or 8 digits (with an optional leading '#'),
+/// where the last two digits of an 8 digit string are the opacity.
+pub(crate) fn parse_hex(spec: &str) -> Result<Color, String> {
+    let digits = spec.trim_start_matches('#');
+    let valid =
+        (digits.len() == 6 || digits.len() == 8) && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(hex(digits))
+    } else {
+        Err(format!("invalid color \"{}\"", spec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(Ok([1.0, 0.0, 0.0, 1.0]), parse_hex("ff0000"));
+        assert_eq!(Ok([0.0, 0.0, 1.0, 0.0]), parse_hex("#0000ff00"));
+        assert!(parse_hex("red").is_err());
+        assert!(parse_hex("fff").is_err());
+    }
+
+    #[test]
+    fn test_theme_spec() {
+        let spec = ThemeSpec {
+            background: Some(String::from("000000")),
+            players: Some(vec![String::from("00ff00")]),
+            player_names: Some(vec![String::from("Lime")]),
+            ..ThemeSpec::default()
+        };
+        let theme = spec.to_theme().unwrap();
+        assert_eq!("Custom", theme.name);
+        assert_eq!([0.0, 0.0, 0.0, 1.0], theme.background);
+        assert_eq!([0.0, 1.0, 0.0, 1.0], theme.players[0]);
+        assert_eq!("Lime", theme.player_names[0]);
+        assert_eq!(Theme::classic().players[1], theme.players[1]);
+
+        let spec = ThemeSpec {
+            food: Some(String::from("yellow")),
+            ..ThemeSpec::default()
+        };
+        assert!(spec.to_theme().is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! This file contains the terminal frontend, which plays the game in a terminal instead of a
+//! window.  The board is drawn with box-drawing characters and ANSI colors, and keys are read
+//! in raw mode, so the game can be played over SSH and on machines without OpenGL.  It drives
+//! the same Game as the window, so the rules are exactly the same.
+
+use crate::game::Game;
+use crate::player::Direction;
+use crate::replay::ReplayRecorder;
+use crate::stats::History;
+use crate::status::{self, Text};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color as TermColor, Print, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, event, execute, queue, terminal};
+use input::Key;
+use piston_graphics::types::Color;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The time between frames drawn in the terminal.
+const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// The color of the arrow drawn on each player's head.
+const HEAD_MARKER_COLOR: Color = [1.0, 1.0, 1.0, 1.0];
+
+/// A single Block of the board as drawn in the terminal.  Each Block is two characters wide,
+/// since characters are about twice as tall as they are wide.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Cell {
+    text: [char; 2],
+    foreground: Color,
+    background: Color,
+}
+
+/// Puts the terminal into raw mode on an alternate screen for as long as it exists, and puts
+/// it back the way it was when dropped, even if the game panics.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            crossterm::style::ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Plays the given Game in the terminal until Escape or Ctrl+C is pressed, recording each
+/// round in the given match history and keeping replays with the given recorder.
+pub fn run(mut game: Game, mut history: History, mut replays: ReplayRecorder) -> io::Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    let mut stdout = io::stdout();
+    let mut last_update = Instant::now();
+
+    loop {
+        if event::poll(FRAME_TIME)? {
+            while event::poll(Duration::ZERO)? {
+                if let Event::Key(key_event) = event::read()? {
+                    if is_quit(key_event) {
+                        return Ok(());
+                    }
+                    if let Some(key) = key_for(key_event) {
+                        replays.key_pressed(key);
+                        game.key_pressed(key);
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let time_elapsed = now.duration_since(last_update).as_secs_f64();
+        let _ = replays.before_update(&game, time_elapsed);
+        game.update(time_elapsed);
+        last_update = now;
+        let events = game.take_events();
+        // errors can't be shown without garbling the board, and the round is still kept in
+        // memory, so a history or replay that can't be saved doesn't interrupt the game
+        let _ = history.update(&game, &events);
+        let _ = replays.after_update(&events);
+
+        draw(&game, &mut stdout)?;
+    }
+}
+
+/// Returns true if the given key event should quit the game.
+fn is_quit(key_event: KeyEvent) -> bool {
+    match key_event.code {
+        KeyCode::Esc => true,
+        KeyCode::Char('c') => key_event.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+/// Returns the key of the window frontend that the given key event stands for, so it can be
+/// handed to Game::key_pressed.  Terminals can't tell the number pad apart from the number
+/// keys, so the 8, 4, 5 and 6 keys stand in for the number pad keys that player 4 is
+/// controlled with.  Key releases (which only some terminals report) are ignored.
+fn key_for(key_event: KeyEvent) -> Option<Key> {
+    if key_event.kind == KeyEventKind::Release {
+        return None;
+    }
+    let key = match key_event.code {
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Return,
+        KeyCode::Char('4') => Key::NumPad4,
+        KeyCode::Char('5') => Key::NumPad5,
+        KeyCode::Char('6') => Key::NumPad6,
+        KeyCode::Char('8') => Key::NumPad8,
+        KeyCode::Char(c) if c.is_ascii_alphabetic() => Key::from(c.to_ascii_lowercase() as u32),
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Draws the board, the heads-up display, the countdown before the round starts and, once
+/// the round is over, the result and how each player crashed, starting in the top left
+/// corner of the terminal.
+fn draw(game: &Game, out: &mut impl Write) -> io::Result<()> {
+    queue!(out, cursor::MoveTo(0, 0))?;
+    let mut rows = board(game);
+    let middle = rows.len() / 2;
+    if let Some(countdown) = status::countdown(game) {
+        overlay_text(&mut rows[middle], &countdown);
+    }
+    if let Some(game_over) = status::game_over(game) {
+        overlay_text(&mut rows[middle], &game_over.result);
+        // how each player crashed, a blank row below the result
+        for (line, crash) in game_over.crashes.iter().enumerate() {
+            if let Some(row) = rows.get_mut(middle + 2 + line) {
+                overlay_text(row, crash);
+            }
+        }
+    }
+
+    for row in &rows {
+        // colors are only sent when they change, to keep frames small over slow connections
+        let mut colors = None;
+        for cell in row {
+            if colors != Some((cell.foreground, cell.background)) {
+                colors = Some((cell.foreground, cell.background));
+                queue!(
+                    out,
+                    SetForegroundColor(term_color(cell.foreground)),
+                    SetBackgroundColor(term_color(cell.background))
+                )?;
+            }
+            queue!(out, Print(cell.text[0]), Print(cell.text[1]))?;
+        }
+        queue!(out, crossterm::style::ResetColor, Print("\r\n"))?;
+    }
+
+    for line in hud(game) {
+        for piece in line {
+            queue!(
+                out,
+                SetForegroundColor(term_color(piece.color)),
+                Print(piece.text)
+            )?;
+        }
+        queue!(
+            out,
+            crossterm::style::ResetColor,
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            Print("\r\n")
+        )?;
+    }
+    out.flush()
+}
+
+/// Returns the Cells of the board, row by row.  The outer edge of the board is drawn as a
+/// box, and any rings closed in sudden death and the obstacles of the arena are filled in
+/// with the border color.
+fn board(game: &Game) -> Vec<Vec<Cell>> {
+    let theme = game.theme();
+    let (width, height) = (game.get_width() as usize, game.get_height() as usize);
+    let border = game.border_width() as usize;
+    let empty = Cell {
+        text: [' ', ' '],
+        foreground: theme.text,
+        background: theme.background,
+    };
+    let mut rows = vec![vec![empty; width]; height];
+
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            let (left, right) = (x == 0, x == width - 1);
+            let (top, bottom) = (y == 0, y == height - 1);
+            let text = match (left, right, top, bottom) {
+                (true, _, true, _) => ['┌', '─'],
+                (_, true, true, _) => ['─', '┐'],
+                (true, _, _, true) => ['└', '─'],
+                (_, true, _, true) => ['─', '┘'],
+                (_, _, true, _) | (_, _, _, true) => ['─', '─'],
+                (true, _, _, _) => ['│', ' '],
+                (_, true, _, _) => [' ', '│'],
+                _ if x < border || x >= width - border || y < border || y >= height - border => {
+                    [' ', ' ']
+                }
+                _ => continue,
+            };
+            cell.text = text;
+            cell.background = theme.border;
+        }
+    }
+
+    for obstacle in game.obstacles() {
+        rows[obstacle.y as usize][obstacle.x as usize].background = theme.border;
+    }
+
+    if let Some(food) = game.food() {
+        rows[food.y as usize][food.x as usize] = Cell {
+            text: ['(', ')'],
+            foreground: theme.food,
+            background: theme.background,
+        };
+    }
+
+    for (index, player) in game.players().iter().enumerate() {
+        let color = game.team_of_player(index).color;
+        for block in player.trail() {
+            rows[block.y as usize][block.x as usize] = Cell {
+                text: [' ', ' '],
+                foreground: theme.text,
+                background: color,
+            };
+        }
+        let head = player.head();
+        let arrow = match player.moving_direction() {
+            Direction::Up => '▲',
+            Direction::Down => '▼',
+            Direction::Left => '◀',
+            Direction::Right => '▶',
+        };
+        rows[head.y as usize][head.x as usize].text = [arrow, ' '];
+        rows[head.y as usize][head.x as usize].foreground = HEAD_MARKER_COLOR;
+    }
+    rows
+}
+
+/// Writes the given text over the middle of a row of Cells, in its color.
+fn overlay_text(row: &mut [Cell], line: &Text) {
+    let chars: Vec<char> = line.text.chars().collect();
+    let start = (row.len() * 2).saturating_sub(chars.len()) / 2;
+    for (offset, &c) in chars.iter().enumerate() {
+        let position = start + offset;
+        if let Some(cell) = row.get_mut(position / 2) {
+            cell.text[position % 2] = c;
+            cell.foreground = line.color;
+        }
+    }
+}
+
+/// Returns the lines of the heads-up display shown below the board, as pieces of text in
+/// their colors.  The text comes from `status::hud`, so it is the same as in the window,
+/// with the sudden death countdown after the status and the players and scores in columns.
+fn hud(game: &Game) -> Vec<Vec<Text>> {
+    let hud = status::hud(game);
+    let mut status = vec![hud.status];
+    if let Some(mut sudden_death) = hud.sudden_death {
+        sudden_death.text.insert_str(0, "   ");
+        status.push(sudden_death);
+    }
+    let columns = |pieces: Vec<Text>| {
+        pieces
+            .into_iter()
+            .map(|piece| Text {
+                text: format!("{:<12}", piece.text),
+                ..piece
+            })
+            .collect()
+    };
+    let quit = Text {
+        text: String::from("Esc: quit"),
+        color: game.theme().text,
+    };
+    vec![
+        status,
+        columns(hud.players),
+        columns(hud.scores),
+        vec![quit],
+    ]
+}
+
+/// Converts a Color into a 24-bit terminal color.
+fn term_color(color: Color) -> TermColor {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    TermColor::Rgb {
+        r: channel(color[0]),
+        g: channel(color[1]),
+        b: channel(color[2]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_for() {
+        let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(Some(Key::W), key_for(press(KeyCode::Char('w'))));
+        assert_eq!(Some(Key::W), key_for(press(KeyCode::Char('W'))));
+        assert_eq!(Some(Key::NumPad8), key_for(press(KeyCode::Char('8'))));
+        assert_eq!(Some(Key::Left), key_for(press(KeyCode::Left)));
+        assert_eq!(None, key_for(press(KeyCode::Tab)));
+        assert!(is_quit(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL
+        )));
+        assert!(!is_quit(press(KeyCode::Char('c'))));
+    }
+
+    #[test]
+    fn test_board() {
+        let game = Game::new(35, 25);
+        let rows = board(&game);
+        assert_eq!(25, rows.len());
+        assert_eq!(['┌', '─'], rows[0][0].text);
+        assert_eq!([' ', '│'], rows[10][34].text);
+        // player 1's head is at (4, 3), moving right
+        assert_eq!(['▶', ' '], rows[3][4].text);
+        assert_eq!(game.team_of_player(0).color, rows[3][2].background);
+        assert_eq!(game.theme().background, rows[10][10].background);
+    }
+
+    #[test]
+    fn test_hud() {
+        let game = Game::new(35, 25);
+        let lines = hud(&game);
+        assert_eq!(4, lines.len());
+        assert_eq!(status::hud(&game).status, lines[0][0]);
+        assert_eq!(12, lines[1][0].text.len());
+        assert_eq!(game.team_of_player(0).color, lines[1][0].color);
+        assert_eq!("Esc: quit", lines[3][0].text);
+    }
+}
@@ -0,0 +1,375 @@
+//! This file contains the browser frontend, which is compiled to WebAssembly and draws the
+//! game on an HTML canvas.  The page (see `web/index.js`) creates a WebGame, passes it key
+//! presses and the time between animation frames, and asks it to draw itself every frame.
+//! The board is drawn through the same drawing code as the desktop window, by a Graphics
+//! backend that fills triangles on the canvas, and the text is drawn by the canvas itself.
+
+use crate::effects::Effects;
+use crate::game::{Game, BOARD_HEIGHT, BOARD_WIDTH};
+use crate::graphics;
+use crate::status::{self, Text, COLUMNS, COUNTDOWN_SIZE, FONT_SIZE, HUD_HEIGHT, RESULT_SIZE};
+use input::Key;
+use piston_graphics::types::Color;
+use piston_graphics::{Context, DrawState, Graphics, ImageSize};
+use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
+
+/// The name the page registers the embedded font under (see `font`).
+const FONT_FAMILY: &str = "AtariClassic";
+
+/// The game running in the page, along with its visual effects.
+#[wasm_bindgen]
+pub struct WebGame {
+    game: Game,
+    effects: Effects,
+}
+
+#[wasm_bindgen]
+impl WebGame {
+    /// Creates a new game with the classic rules.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WebGame {
+        WebGame {
+            game: Game::new(BOARD_WIDTH, BOARD_HEIGHT),
+            effects: Effects::new(),
+        }
+    }
+
+    /// Returns the width of everything that is drawn, in points.  The page should give the
+    /// canvas the same aspect ratio, although anything else is letterboxed.
+    pub fn width(&self) -> f64 {
+        self.game.get_width() as f64 * graphics::POINTS_PER_BLOCK
+    }
+
+    /// Returns the height of everything that is drawn (the board and the heads-up display),
+    /// in points.
+    pub fn height(&self) -> f64 {
+        (self.game.get_height() + HUD_HEIGHT) as f64 * graphics::POINTS_PER_BLOCK
+    }
+
+    /// Handles a key press, given as the `code` of a KeyboardEvent (e.g. "KeyW" or
+    /// "ArrowUp").  Returns true if the game uses the key, in which case the page should stop
+    /// the browser from acting on it (e.g. scrolling with the arrow keys).
+    pub fn key_down(&mut self, code: &str) -> bool {
+        match key_for(code) {
+            Some(key) => {
+                self.game.key_pressed(key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advances the game and its effects by the given number of seconds.
+    pub fn update(&mut self, time_elapsed: f64) {
+        self.game.update(time_elapsed);
+        let events = self.game.take_events();
+        self.effects.update(&self.game, &events, time_elapsed);
+    }
+
+    /// Draws the game on a canvas of the given size (in pixels), scaled to fit it.
+    pub fn draw(&self, canvas: &CanvasRenderingContext2d, width: f64, height: f64) {
+        let logical_size = [self.width(), self.height()];
+        let mut g = Canvas {
+            context: canvas,
+            size: [width, height],
+        };
+        g.clear_color([0.0, 0.0, 0.0, 1.0]);
+
+        let c = graphics::fit_to_view(&Context::new_abs(width, height), logical_size);
+        let theme = self.game.theme();
+        piston_graphics::rectangle(
+            theme.background,
+            [0.0, 0.0, logical_size[0], logical_size[1]],
+            c.transform,
+            &mut g,
+        );
+        self.game.draw(&c, &mut g);
+        self.effects.draw(&c, &mut g);
+        graphics::draw_rectangle(
+            theme.hud,
+            0,
+            self.game.get_height(),
+            self.game.get_width(),
+            HUD_HEIGHT,
+            &c,
+            &mut g,
+        );
+        let game_over = status::game_over(&self.game);
+        if let Some(game_over) = &game_over {
+            graphics::draw_rectangle(
+                graphics::faded(game_over.tint, theme.overlay_opacity),
+                0,
+                0,
+                self.game.get_width(),
+                self.game.get_height(),
+                &c,
+                &mut g,
+            );
+        }
+
+        // Text is drawn by the canvas, in points, using the same scale and offset as the
+        // rest of the game.
+        let scale = (width / logical_size[0]).min(height / logical_size[1]);
+        let _ = canvas.set_transform(
+            scale,
+            0.0,
+            0.0,
+            scale,
+            (width - logical_size[0] * scale) / 2.0,
+            (height - logical_size[1] * scale) / 2.0,
+        );
+        self.draw_text(canvas, game_over.as_ref());
+        let _ = canvas.reset_transform();
+    }
+
+    /// Draws the text of the heads-up display, the countdown before the round starts and,
+    /// once the round is over, the given game-over screen.  The text comes from `status.rs`,
+    /// so it is the same as in the desktop window.
+    fn draw_text(&self, canvas: &CanvasRenderingContext2d, game_over: Option<&status::GameOver>) {
+        let game = &self.game;
+        let block = graphics::POINTS_PER_BLOCK;
+        let width = self.width();
+        let column_width = (width - 16.0) / COLUMNS as f64;
+        let row_middle = |row: u32| (game.get_height() + row) as f64 * block + block / 2.0;
+        let middle = game.get_height() as f64 * block / 2.0;
+        canvas.set_text_baseline("middle");
+
+        let hud = status::hud(game);
+        fill_text(canvas, &hud.status, FONT_SIZE, "left", 8.0, row_middle(0));
+        if let Some(sudden_death) = &hud.sudden_death {
+            fill_text(
+                canvas,
+                sudden_death,
+                FONT_SIZE,
+                "right",
+                width - 8.0,
+                row_middle(0),
+            );
+        }
+        for (index, player) in hud.players.iter().enumerate() {
+            let x = 8.0 + column_width * index as f64;
+            fill_text(canvas, player, FONT_SIZE, "left", x, row_middle(1));
+        }
+        for (index, score) in hud.scores.iter().enumerate() {
+            let x = 8.0 + column_width * index as f64;
+            fill_text(canvas, score, FONT_SIZE, "left", x, row_middle(2));
+        }
+
+        if let Some(countdown) = status::countdown(game) {
+            fill_text(
+                canvas,
+                &countdown,
+                COUNTDOWN_SIZE,
+                "center",
+                width / 2.0,
+                middle,
+            );
+        }
+
+        if let Some(game_over) = game_over {
+            fill_text(
+                canvas,
+                &game_over.result,
+                RESULT_SIZE,
+                "center",
+                width / 2.0,
+                middle,
+            );
+            // how each player crashed, listed below the result
+            for (line, crash) in game_over.crashes.iter().enumerate() {
+                let y = middle + block * (1.5 + line as f64);
+                fill_text(canvas, crash, FONT_SIZE, "center", width / 2.0, y);
+            }
+        }
+    }
+}
+
+impl Default for WebGame {
+    fn default() -> Self {
+        WebGame::new()
+    }
+}
+
+/// Returns the contents of the font the game's text is drawn in, which is embedded in the
+/// WebAssembly module.  The page registers it as a FontFace named "AtariClassic" before
+/// the game is first drawn.
+#[wasm_bindgen]
+pub fn font() -> Vec<u8> {
+    crate::FONT.to_vec()
+}
+
+/// Returns the key of the window frontend that the given KeyboardEvent code stands for, so
+/// it can be handed to Game::key_pressed.  Codes name physical keys, so the controls stay in
+/// the same place whatever the keyboard layout.
+fn key_for(code: &str) -> Option<Key> {
+    let key = match code {
+        "ArrowUp" => Key::Up,
+        "ArrowDown" => Key::Down,
+        "ArrowLeft" => Key::Left,
+        "ArrowRight" => Key::Right,
+        "Enter" | "NumpadEnter" => Key::Return,
+        "Numpad4" => Key::NumPad4,
+        "Numpad5" => Key::NumPad5,
+        "Numpad6" => Key::NumPad6,
+        "Numpad8" => Key::NumPad8,
+        _ => {
+            // letter keys have codes from "KeyA" to "KeyZ"
+            let letter = code
+                .strip_prefix("Key")
+                .filter(|letter| letter.len() == 1)?;
+            let letter = letter.chars().next().filter(char::is_ascii_uppercase)?;
+            Key::from(letter.to_ascii_lowercase() as u32)
+        }
+    };
+    Some(key)
+}
+
+/// Draws a line of text on the canvas in its color and the given font size, with `align`
+/// being the canvas's name for which part of the text lies at `x`.
+fn fill_text(
+    canvas: &CanvasRenderingContext2d,
+    line: &Text,
+    size: u32,
+    align: &str,
+    x: f64,
+    y: f64,
+) {
+    canvas.set_font(&format!("{}px {}", size, FONT_FAMILY));
+    canvas.set_text_align(align);
+    canvas.set_fill_style_str(&css_color(line.color));
+    let _ = canvas.fill_text(&line.text, x.round(), y.round());
+}
+
+/// Converts a Color into a CSS color string.
+fn css_color(color: Color) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "rgba({}, {}, {}, {})",
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        color[3].clamp(0.0, 1.0)
+    )
+}
+
+/// A Graphics backend that draws on a canvas.  Piston draws everything as lists of
+/// triangles, which are filled on the canvas as a single path per list, so overlapping
+/// triangles of a translucent shape are only drawn once.
+struct Canvas<'a> {
+    context: &'a CanvasRenderingContext2d,
+    size: [f64; 2], // the size of the canvas, in pixels
+}
+
+/// The canvas backend draws no images, so its textures are empty.
+struct NoTexture;
+
+impl ImageSize for NoTexture {
+    fn get_size(&self) -> (u32, u32) {
+        (0, 0)
+    }
+}
+
+impl Canvas<'_> {
+    /// Adds the given triangles to the current path.  Vertices are given in normalized
+    /// device coordinates, from (-1, -1) at the bottom left of the canvas to (1, 1) at the
+    /// top right.
+    fn add_triangles(&self, vertices: &[[f32; 2]]) {
+        let [width, height] = self.size;
+        let point = |[x, y]: [f32; 2]| {
+            (
+                (x as f64 + 1.0) / 2.0 * width,
+                (1.0 - y as f64) / 2.0 * height,
+            )
+        };
+        for triangle in vertices.chunks_exact(3) {
+            let (x, y) = point(triangle[0]);
+            self.context.move_to(x, y);
+            for &vertex in &triangle[1..] {
+                let (x, y) = point(vertex);
+                self.context.line_to(x, y);
+            }
+            self.context.close_path();
+        }
+    }
+}
+
+impl Graphics for Canvas<'_> {
+    type Texture = NoTexture;
+
+    fn clear_color(&mut self, color: Color) {
+        let [width, height] = self.size;
+        self.context.clear_rect(0.0, 0.0, width, height);
+        self.context.set_fill_style_str(&css_color(color));
+        self.context.fill_rect(0.0, 0.0, width, height);
+    }
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        self.context.begin_path();
+        f(&mut |vertices| self.add_triangles(vertices));
+        self.context.set_fill_style_str(&css_color(*color));
+        self.context.fill();
+    }
+
+    fn tri_list_c<F>(&mut self, _draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        // each triangle is filled in the color of its first vertex
+        f(&mut |vertices, colors| {
+            for (triangle, color) in vertices.chunks_exact(3).zip(colors.chunks_exact(3)) {
+                self.context.begin_path();
+                self.add_triangles(triangle);
+                self.context.set_fill_style_str(&css_color(color[0]));
+                self.context.fill();
+            }
+        });
+    }
+
+    fn tri_list_uv<F>(&mut self, _: &DrawState, _: &[f32; 4], _: &NoTexture, _: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+    }
+
+    fn tri_list_uv_c<F>(&mut self, _: &DrawState, _: &NoTexture, _: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER_SPEED;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_key_for() {
+        assert_eq!(Some(Key::W), key_for("KeyW"));
+        assert_eq!(Some(Key::Left), key_for("ArrowLeft"));
+        assert_eq!(Some(Key::NumPad8), key_for("Numpad8"));
+        assert_eq!(None, key_for("Key"));
+        assert_eq!(None, key_for("Tab"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_round() {
+        // player 1 is handed to the AI, and with nobody steering player 2 drives into a wall
+        // and the round ends
+        let mut web_game = WebGame::new();
+        assert!(web_game.key_down("KeyP"));
+        for _ in 0..1000 {
+            web_game.update(PLAYER_SPEED);
+        }
+        assert!(web_game.game.is_game_over());
+        assert!(web_game.key_down("Enter"));
+        assert!(!web_game.game.is_game_over());
+    }
+}
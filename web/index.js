@@ -0,0 +1,37 @@
+// Runs the game in the page.  `pkg/` is generated by wasm-bindgen (see the README).
+import init, { WebGame, font } from "./pkg/bloxide.js";
+
+async function main() {
+  await init();
+
+  // the font is embedded in the WebAssembly module, so it's registered from there
+  const face = new FontFace("AtariClassic", font());
+  document.fonts.add(await face.load());
+
+  const canvas = document.getElementById("game");
+  const context = canvas.getContext("2d");
+  const game = new WebGame();
+
+  window.addEventListener("keydown", (event) => {
+    if (game.key_down(event.code)) {
+      event.preventDefault();
+    }
+  });
+
+  let last = performance.now();
+  function frame(now) {
+    // the canvas is drawn at the resolution of the screen, and the game letterboxes itself
+    const scale = window.devicePixelRatio || 1;
+    canvas.width = Math.round(canvas.clientWidth * scale);
+    canvas.height = Math.round(canvas.clientHeight * scale);
+
+    // long pauses (e.g. while the tab is hidden) don't make the players jump ahead
+    game.update(Math.min((now - last) / 1000, 0.25));
+    last = now;
+    game.draw(context, canvas.width, canvas.height);
+    requestAnimationFrame(frame);
+  }
+  requestAnimationFrame(frame);
+}
+
+main();